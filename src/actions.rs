@@ -1,7 +1,11 @@
 use crate::entities::*;
+use crate::ipc::protocol::IpcEvent;
+use crate::keyboard_devices::apply_all_device_configs;
+pub(crate) use crate::shortcuts::{
+  CycleDirection, CycleScope, Direction, VerticalDirection, WorkspaceSelector,
+};
 use crate::window_manager::CascadeWindowManager;
 use log::{debug, error, trace, warn};
-use serde::{Deserialize, Serialize};
 use std::cmp;
 use std::{cmp::Ordering, rc::Rc};
 use window::WindowAnimations;
@@ -22,35 +26,74 @@ impl Default for ArrangeWindowOptions {
   }
 }
 
+/// The width of the column, which follows its most recently used window.
+///
+/// Running resizes are taken to be done so that columns are laid out where
+/// they end up.
+pub(crate) fn target_column_width(
+  wm: &CascadeWindowManager,
+  workspace: &Workspace,
+  column: &Column,
+) -> i32 {
+  workspace
+    .active_window_in_column(column)
+    .map(|window| wm.animation_manager.target_window_size(&window).width())
+    .unwrap_or(0)
+}
+
 pub(crate) fn arrange_windows_workspace_options(
   wm: &CascadeWindowManager,
   workspace: Rc<Workspace>,
   options: ArrangeWindowOptions,
 ) {
+  wm.session.mark_dirty();
   if let Some(output) = wm.output_by_workspace(&workspace) {
+    let gaps = wm.config().gaps;
+    let layout_area = gaps.layout_area(output.usable_area());
+    let slide_offset = workspace.slide_offset();
+    let slide = Displacement {
+      dx: 0,
+      dy: slide_offset,
+    };
+    let fullscreen_extents = output.extents();
+    let fullscreen_window = workspace.fullscreen_window();
     let positions = workspace
-      .windows()
+      .columns()
       .iter()
-      .cloned()
-      .scan(output.extents().left(), |next_x, window| {
+      .map(|column| {
+        let windows = column
+          .windows()
+          .iter()
+          .cloned()
+          .zip(column.heights().iter().cloned())
+          .collect::<Vec<_>>();
+        // A fullscreen window pushes the neighbouring columns out of the output
+        let width = match fullscreen_window {
+          Some(ref fullscreen_window) if column.contains(fullscreen_window) => {
+            output.extents().width()
+          }
+          _ => target_column_width(wm, &workspace, column),
+        };
+        (windows, width)
+      })
+      .scan(layout_area.left(), |next_x, (windows, width)| {
         let x = *next_x;
-        *next_x = x + window.size().width();
-        Some((window, x))
+        *next_x = x + width + gaps.inner;
+        Some((windows, x, width))
       })
       .collect::<Vec<_>>();
 
     let mut scroll_left = workspace.scroll_left();
-    let active_window = wm.active_window();
+    let active_window = fullscreen_window.clone().or_else(|| wm.active_window());
     // Enusure that the focused window is visble
     if let Some(window) = active_window {
-      for (w, window_x) in positions.iter() {
-        if *w == window {
-          let extents_left = output.extents().left();
-          let extents_right = output.extents().right();
-          let window_width = window.size().width();
+      for (windows, column_x, column_width) in positions.iter() {
+        if windows.iter().any(|(w, _)| *w == window) {
+          let extents_left = layout_area.left();
+          let extents_right = layout_area.right();
 
-          let x_window_left = *window_x;
-          let x_window_right = window_x + window_width;
+          let x_window_left = *column_x;
+          let x_window_right = column_x + column_width;
 
           let x_workspace_left = scroll_left + extents_left;
           let x_workspace_right = scroll_left + extents_right;
@@ -69,42 +112,117 @@ pub(crate) fn arrange_windows_workspace_options(
 
     let gesture_window = wm.gesture.borrow().window();
 
-    for (window, x) in positions {
-      let is_gesture_window = Some(&window) == gesture_window.as_ref();
-      if !options.force_set && is_gesture_window {
-        continue;
-      }
-
-      let height = cmp::min(
-        output.extents().height(),
-        window.max_height().unwrap_or(i32::MAX as u32) as i32,
-      );
+    for (windows, x, width) in positions {
+      let num_windows = windows.len();
+      let rows_height = layout_area.height() - gaps.inner * (num_windows as i32 - 1);
+      let mut next_y = layout_area.top();
+      let mut remaining_height = rows_height;
 
-      let y = output.extents().top() + (output.extents().height() - height) / 2;
+      for (index, (window, height_fraction)) in windows.into_iter().enumerate() {
+        // The last window takes what is left to avoid gaps from rounding
+        let row_height = if index + 1 == num_windows {
+          remaining_height
+        } else {
+          (rows_height as f32 * height_fraction).round() as i32
+        };
+        let row_y = next_y;
+        next_y += row_height + gaps.inner;
+        remaining_height -= row_height;
+
+        let is_gesture_window = Some(&window) == gesture_window.as_ref();
+        if !options.force_set && is_gesture_window {
+          continue;
+        }
+        if Some(&window) == fullscreen_window.as_ref() {
+          set_fullscreen_extents(wm, window, &fullscreen_extents, slide);
+          continue;
+        }
 
-      let extents = Rectangle {
-        top_left: Point { x, y },
-        size: window.size().with_height(height),
-      } + Displacement {
-        dx: -scroll_left,
-        dy: 0,
-      };
+        let height = cmp::min(
+          row_height,
+          window.max_height().unwrap_or(i32::MAX as u32) as i32,
+        );
 
-      if extents.size() == window.size() {
-        if is_gesture_window || !options.animate {
+        let y = row_y + (row_height - height) / 2;
+
+        let extents = Rectangle {
+          top_left: Point { x, y },
+          size: window.size().with_width(width).with_height(height),
+        } + Displacement {
+          dx: -scroll_left,
+          dy: 0,
+        };
+
+        if slide_offset != 0 {
+          // The windows are translated by the slide, so they are put in place
+          // directly
+          place_sliding_window(wm, &window, extents.top_left, slide);
+          if extents.size() != window.size() {
+            wm.animation_manager.set_window_size(window, extents.size());
+          }
+        } else if is_gesture_window || !options.animate {
           wm.animation_manager
-            .set_window_position(window, extents.top_left);
+            .set_window_position(window.clone(), extents.top_left);
+          if extents.size() != window.size() {
+            wm.animation_manager.set_window_size(window, extents.size());
+          }
         } else {
           wm.animation_manager
-            .animate_window_position(window, extents.top_left);
+            .animate_window_position(window.clone(), extents.top_left);
+          wm.animation_manager
+            .animate_window_size(window, extents.size());
         }
-      } else {
-        window.set_extents(&extents);
+      }
+    }
+
+    for window in workspace.floating_windows().iter().cloned() {
+      // Keep the floating layer stacked above the strip
+      wm.window_manager.raise_window(&window);
+      if Some(&window) == gesture_window.as_ref() {
+        continue;
+      }
+      if Some(&window) == fullscreen_window.as_ref() {
+        set_fullscreen_extents(wm, window, &fullscreen_extents, slide);
+        continue;
+      }
+      // Floating windows keep their position, unless the workspace moved to
+      // another output
+      if !output.extents().contains(&window.extents().top_left()) {
+        center_floating_window(wm, window, &output);
       }
     }
   }
 }
 
+/// Moves a window of a sliding workspace without animating, keeping it
+/// translated by the slide
+fn place_sliding_window(
+  wm: &CascadeWindowManager,
+  window: &Rc<Window>,
+  top_left: Point,
+  slide: Displacement,
+) {
+  wm.animation_manager.stop_window_position(window);
+  window.move_to(top_left);
+  window.set_translate(slide);
+}
+
+fn set_fullscreen_extents(
+  wm: &CascadeWindowManager,
+  window: Rc<Window>,
+  extents: &Rectangle,
+  slide: Displacement,
+) {
+  wm.window_manager.raise_window(&window);
+  if window.extents() != *extents {
+    window.set_extents(extents);
+  }
+  if slide.dy != 0 {
+    wm.animation_manager.stop_window_position(&window);
+    window.set_translate(slide);
+  }
+}
+
 pub(crate) fn arrange_windows_workspace(wm: &CascadeWindowManager, workspace: Rc<Workspace>) {
   arrange_windows_workspace_options(wm, workspace, ArrangeWindowOptions::default());
 }
@@ -115,18 +233,6 @@ pub(crate) fn arrange_windows_all_workspaces(wm: &CascadeWindowManager) {
   }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
-pub(crate) enum Direction {
-  Left,
-  Right,
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
-pub(crate) enum VerticalDirection {
-  Up,
-  Down,
-}
-
 pub(crate) fn navigate_first(wm: &CascadeWindowManager) {
   if let Some(active_workspace) = wm.mru_workspaces().top() {
     if let Some(window) = active_workspace.windows().first() {
@@ -161,6 +267,84 @@ pub(crate) fn navigate(wm: &CascadeWindowManager, direction: Direction) {
         trace!("Navigate to right monitor");
         navigate_monitor(wm, Direction::Right, WorkspacePosition::Start);
       }
+      Direction::Up | Direction::Down => {}
+    }
+  }
+}
+
+pub(crate) fn cycle_windows(
+  wm: &CascadeWindowManager,
+  scope: CycleScope,
+  direction: CycleDirection,
+) {
+  if wm.window_cycle.borrow().is_none() {
+    let active_workspace = wm.mru_workspaces().top().cloned();
+    let active_output = active_workspace
+      .as_ref()
+      .and_then(|workspace| wm.output_by_workspace(workspace));
+    let windows = match scope {
+      CycleScope::Workspace => active_workspace
+        .map(|workspace| workspace.mru_windows().iter().cloned().collect())
+        .unwrap_or_default(),
+      CycleScope::Output => wm
+        .mru_windows()
+        .iter()
+        .filter(|window| {
+          let output = wm.output_by_window(window);
+          wm.workspace_by_window(window).is_some() && (output.is_none() || output == active_output)
+        })
+        .cloned()
+        .collect(),
+      CycleScope::All => wm
+        .mru_windows()
+        .iter()
+        .filter(|window| wm.workspace_by_window(window).is_some())
+        .cloned()
+        .collect(),
+    };
+    trace!("Starting to cycle {} windows", windows.len());
+    *wm.window_cycle.borrow_mut() = WindowCycle::new(windows, active_output);
+  }
+
+  let window = wm.window_cycle.borrow_mut().as_mut().map(|cycle| {
+    cycle.step(direction == CycleDirection::Next);
+    cycle.current()
+  });
+  if let Some(window) = window {
+    wm.window_manager.focus_window(window);
+  }
+}
+
+/// Ends an ongoing CycleWindows, making the previewed window the most
+/// recently used.
+pub(crate) fn finish_cycling_windows(wm: &CascadeWindowManager) {
+  let cycle = wm.window_cycle.borrow_mut().take();
+  if let Some(cycle) = cycle {
+    let window = cycle.current();
+    trace!("Finished cycling windows at {:?}", window.title());
+    wm.promote_window(&window);
+    if let Some(workspace) = wm.workspace_by_window(&window) {
+      wm.show_workspace(&workspace, None);
+    }
+  }
+}
+
+pub(crate) fn pull_window(wm: &CascadeWindowManager, direction: Direction) {
+  if let Some(active_window) = wm.active_window() {
+    if let Some(workspace) = wm.workspace_by_window(&active_window) {
+      if workspace.pull_window(&active_window, direction).is_ok() {
+        arrange_windows_workspace(wm, workspace);
+      }
+    }
+  }
+}
+
+pub(crate) fn expel_window(wm: &CascadeWindowManager) {
+  if let Some(active_window) = wm.active_window() {
+    if let Some(workspace) = wm.workspace_by_window(&active_window) {
+      if workspace.expel_window(&active_window).is_ok() {
+        arrange_windows_workspace(wm, workspace);
+      }
     }
   }
 }
@@ -182,6 +366,7 @@ pub(crate) fn move_window(wm: &CascadeWindowManager, direction: Direction) {
             trace!("Move window to right monitor");
             move_window_monitor(wm, Direction::Right, WorkspacePosition::Start);
           }
+          Direction::Up | Direction::Down => {}
         }
       }
     }
@@ -236,7 +421,13 @@ fn get_output_by_direction(wm: &CascadeWindowManager, direction: Direction) -> O
     fn output_x_position(a: &Rc<Output>, b: &Rc<Output>) -> Ordering {
       a.extents().left().cmp(&b.extents().left())
     }
-    outputs.sort_by(output_x_position);
+    fn output_y_position(a: &Rc<Output>, b: &Rc<Output>) -> Ordering {
+      a.extents().top().cmp(&b.extents().top())
+    }
+    match direction {
+      Direction::Left | Direction::Right => outputs.sort_by(output_x_position),
+      Direction::Up | Direction::Down => outputs.sort_by(output_y_position),
+    }
 
     let index = outputs
       .iter()
@@ -246,8 +437,8 @@ fn get_output_by_direction(wm: &CascadeWindowManager, direction: Direction) -> O
       .expect("active_output not found") as isize;
 
     let index = match direction {
-      Direction::Left => index - 1,
-      Direction::Right => index + 1,
+      Direction::Left | Direction::Up => index - 1,
+      Direction::Right | Direction::Down => index + 1,
     };
 
     if index >= 0 {
@@ -264,6 +455,13 @@ pub(crate) fn navigate_workspace(wm: &CascadeWindowManager, direction: VerticalD
 
   if let Some(workspace) = next_workspace {
     trace!("Focusing workspace by direction {:?}", direction);
+    wm.focus_workspace_from(&workspace, Some(direction));
+  }
+}
+
+pub(crate) fn navigate_to_workspace(wm: &CascadeWindowManager, selector: &WorkspaceSelector) {
+  if let Some(workspace) = wm.workspace_by_selector(selector) {
+    trace!("Focusing workspace {:?}", selector);
     wm.focus_workspace(&workspace);
   }
 }
@@ -286,8 +484,8 @@ pub(crate) fn navigate_monitor(
       WorkspacePosition::ActiveWindow => workspace.mru_windows().top().cloned(),
       WorkspacePosition::End => workspace.windows().last().cloned(),
       WorkspacePosition::Start => workspace.windows().first().cloned(),
-      WorkspacePosition::Coordinate(_) => {
-        error!("Can not navigate monitor by coordinate");
+      WorkspacePosition::Coordinate(_) | WorkspacePosition::Column(_) => {
+        error!("Can not navigate monitor by coordinate or column");
         return;
       }
     };
@@ -312,8 +510,13 @@ pub(crate) fn move_specified_window_to_workspace(
   position: WorkspacePosition,
 ) {
   if let Some(from_workspace) = wm.workspace_by_window(&window) {
+    let floating = from_workspace.is_floating(&window);
     from_workspace.remove_window(&window);
-    to_workspace.add_window(window.clone(), position);
+    if floating {
+      to_workspace.add_floating_window(window.clone());
+    } else {
+      to_workspace.add_window(window.clone(), position);
+    }
     wm.focus_workspace(to_workspace);
     arrange_windows_all_workspaces(wm);
   }
@@ -341,6 +544,13 @@ pub(crate) fn move_window_workspace(wm: &CascadeWindowManager, direction: Vertic
   }
 }
 
+pub(crate) fn move_window_to_workspace(wm: &CascadeWindowManager, selector: &WorkspaceSelector) {
+  if let Some(to_workspace) = wm.workspace_by_selector(selector) {
+    trace!("Moving active window to workspace {:?}", selector);
+    move_active_window_to_workspace(wm, &to_workspace, WorkspacePosition::ActiveWindow);
+  }
+}
+
 pub(crate) fn move_window_monitor(
   wm: &CascadeWindowManager,
   direction: Direction,
@@ -367,41 +577,41 @@ pub(crate) fn move_window_monitor(
 
 pub(crate) fn resize_window(wm: &CascadeWindowManager, window: Rc<Window>, steps: &Vec<f32>) {
   if let Some(output) = wm.output_by_window(&window) {
-    let output_width = output.extents().width() as f32;
-    let window_width = window.size().width as f32;
+    let gaps = wm.config().gaps;
+    let layout_area = gaps.layout_area(output.usable_area());
+    // Steps are taken from where a running resize is going, so that they can
+    // be cycled quickly
+    let target_size = wm.animation_manager.target_window_size(&window);
+    let window_width = target_size.width;
 
     let mut did_resize = false;
     for step in steps.iter().cloned() {
-      if window_width < (output_width * step).floor() {
+      let step_width = gaps.column_width(&layout_area, step);
+      if window_width < step_width {
         trace!(
-          "resize_window: {} < {} * {}",
+          "resize_window: {} < {} ({})",
           window_width,
-          output_width,
+          step_width,
           step
         );
-        window.resize(
-          window
-            .size()
-            .with_width((output_width * step).round() as i32),
-        );
+        wm.animation_manager
+          .animate_window_size(window.clone(), target_size.with_width(step_width));
         did_resize = true;
         break;
       } else {
         trace!(
-          "resize_window: {} >= {} * {}",
+          "resize_window: {} >= {} ({})",
           window_width,
-          output_width,
+          step_width,
           step
         );
       }
     }
     if !did_resize {
       if let Some(first_step) = steps.first().cloned() {
-        window.resize(
-          window
-            .size()
-            .with_width((output_width * first_step).round() as i32),
-        );
+        let width = gaps.column_width(&layout_area, first_step);
+        wm.animation_manager
+          .animate_window_size(window.clone(), target_size.with_width(width));
       } else {
         error!("resize_window needs at least one step defined");
       }
@@ -422,13 +632,18 @@ pub(crate) fn center_window(wm: &CascadeWindowManager) {
   if let Some(window) = wm.active_window() {
     if let Some(workspace) = wm.workspace_by_window(&window) {
       if let Some(output) = wm.output_by_workspace(&workspace) {
-        let output_left = output.extents().left();
-        let output_width = output.extents().width();
+        if workspace.is_floating(&window) {
+          center_floating_window(wm, window, &output);
+          return;
+        }
+
+        let layout_area = wm.layout_area(&output);
         let current_scroll_left = workspace.scroll_left();
 
-        let scroll_left =
-          window.extents().left() + current_scroll_left - output_left - output_width / 2
-            + window.size().width / 2;
+        let scroll_left = window.extents().left() + current_scroll_left
+          - layout_area.left()
+          - layout_area.width() / 2
+          + window.size().width / 2;
 
         workspace.set_scroll_left(scroll_left);
 
@@ -440,10 +655,173 @@ pub(crate) fn center_window(wm: &CascadeWindowManager) {
   }
 }
 
+pub(crate) fn center_floating_window(
+  wm: &CascadeWindowManager,
+  window: Rc<Window>,
+  output: &Output,
+) {
+  let layout_area = wm.layout_area(output);
+  let top_left = Point {
+    x: layout_area.left() + (layout_area.width() - window.size().width()) / 2,
+    y: layout_area.top() + (layout_area.height() - window.size().height()) / 2,
+  };
+  let slide_offset = wm
+    .workspace_by_window(&window)
+    .map_or(0, |workspace| workspace.slide_offset());
+  if slide_offset != 0 {
+    let slide = Displacement {
+      dx: 0,
+      dy: slide_offset,
+    };
+    place_sliding_window(wm, &window, top_left, slide);
+  } else {
+    wm.animation_manager.set_window_position(window, top_left);
+  }
+}
+
+pub(crate) fn toggle_floating(wm: &CascadeWindowManager) {
+  if let Some(window) = wm.active_window() {
+    if let Some(workspace) = wm.workspace_by_window(&window) {
+      if workspace.is_floating(&window) {
+        trace!("Tiling window \"{:?}\"", window.title());
+        workspace.remove_window(&window);
+        workspace.add_window(window, WorkspacePosition::ActiveWindow);
+      } else {
+        trace!("Floating window \"{:?}\"", window.title());
+        workspace.remove_window(&window);
+        workspace.add_floating_window(window.clone());
+        if let Some(output) = wm.output_by_workspace(&workspace) {
+          center_floating_window(wm, window, &output);
+        }
+      }
+      arrange_windows_workspace(wm, workspace);
+    }
+  }
+}
+
+pub(crate) fn move_to_scratchpad(wm: &CascadeWindowManager, name: Option<String>) {
+  if let Some(window) = wm.active_window() {
+    trace!(
+      "Moving window \"{:?}\" to the scratchpad as {:?}",
+      window.title(),
+      name
+    );
+    wm.scratchpad.add(window.clone(), name);
+    hide_scratchpad_window(wm, window);
+  }
+}
+
+pub(crate) fn toggle_scratchpad(
+  wm: &CascadeWindowManager,
+  name: Option<&str>,
+  app_id: Option<&str>,
+) {
+  let window = match wm.scratchpad.find(name, app_id) {
+    Some(window) => window,
+    None => {
+      debug!(
+        "No scratchpad window with name {:?} and app_id {:?}",
+        name, app_id
+      );
+      return;
+    }
+  };
+  let active_workspace = match wm.mru_workspaces().top().cloned() {
+    Some(workspace) => workspace,
+    None => return,
+  };
+
+  if wm.workspace_by_window(&window).as_ref() == Some(&active_workspace) {
+    hide_scratchpad_window(wm, window);
+  } else {
+    show_scratchpad_window(wm, window.clone(), &active_workspace);
+    wm.window_manager.focus_window(window);
+  }
+}
+
+/// Shows a scratchpad window floating and centered on the workspace, taking
+/// it from any other workspace it is shown on
+pub(crate) fn show_scratchpad_window(
+  wm: &CascadeWindowManager,
+  window: Rc<Window>,
+  workspace: &Rc<Workspace>,
+) {
+  trace!("Showing scratchpad window \"{:?}\"", window.title());
+  if let Some(from_workspace) = wm.workspace_by_window(&window) {
+    from_workspace.remove_window(&window);
+    arrange_windows_workspace(wm, from_workspace);
+  }
+  wm.add_mru_window(window.clone());
+  workspace.add_floating_window(window.clone());
+  window.set_hidden(false);
+  if let Some(output) = wm.output_by_workspace(workspace) {
+    center_floating_window(wm, window, &output);
+  }
+}
+
+fn hide_scratchpad_window(wm: &CascadeWindowManager, window: Rc<Window>) {
+  trace!("Hiding scratchpad window \"{:?}\"", window.title());
+  window.set_hidden(true);
+  wm.remove_mru_window(&window);
+  if let Some(workspace) = wm.workspace_by_window(&window) {
+    if workspace.fullscreen_window().as_ref() == Some(&window) {
+      set_window_fullscreen(wm, window.clone(), false);
+    }
+    workspace.remove_window(&window);
+    if wm.mru_workspaces().top() == Some(&workspace) {
+      wm.focus_workspace(&workspace);
+    }
+    arrange_windows_workspace(wm, workspace);
+  }
+}
+
+pub(crate) fn set_window_fullscreen(
+  wm: &CascadeWindowManager,
+  window: Rc<Window>,
+  fullscreen: bool,
+) {
+  if let Some(workspace) = wm.workspace_by_window(&window) {
+    let current_fullscreen_window = workspace.fullscreen_window();
+    if fullscreen {
+      if current_fullscreen_window.as_ref() == Some(&window) {
+        return;
+      }
+      if let Some(current_fullscreen_window) = current_fullscreen_window {
+        set_window_fullscreen(wm, current_fullscreen_window, false);
+      }
+      trace!("Fullscreening window \"{:?}\"", window.title());
+      workspace.set_fullscreen_window(window.clone());
+      window.set_fullscreen(true);
+    } else if current_fullscreen_window.as_ref() == Some(&window) {
+      trace!("Leaving fullscreen for window \"{:?}\"", window.title());
+      if let Some((window, extents)) = workspace.take_fullscreen_window() {
+        window.set_fullscreen(false);
+        window.set_extents(&extents);
+      }
+    } else if window.fullscreen() {
+      window.set_fullscreen(false);
+    }
+    arrange_windows_workspace(wm, workspace);
+  }
+}
+
+pub(crate) fn toggle_fullscreen(wm: &CascadeWindowManager) {
+  if let Some(window) = wm.active_window() {
+    let fullscreen = wm.workspace_by_window(&window).map_or(false, |workspace| {
+      workspace.fullscreen_window().as_ref() == Some(&window)
+    });
+    set_window_fullscreen(wm, window, !fullscreen);
+  }
+}
+
 pub(crate) fn switch_keyboard_layout(wm: &CascadeWindowManager) {
+  let config = wm.config();
+  if config.keyboard_layouts.is_empty() {
+    warn!("switch_keyboard_layout: No keyboard_layouts configured");
+    return;
+  }
   let current_layout = &wm.config_manager.config().keyboard;
-  let current_index = wm
-    .config
+  let current_index = config
     .keyboard_layouts
     .iter()
     .enumerate()
@@ -456,12 +834,19 @@ pub(crate) fn switch_keyboard_layout(wm: &CascadeWindowManager) {
     })
     // If we didn't find the layout, default to the last so that
     // we increment to the first
-    .unwrap_or(wm.config.keyboard_layouts.len() - 1);
+    .unwrap_or(config.keyboard_layouts.len() - 1);
 
-  let next_index = (current_index + 1) % wm.config.keyboard_layouts.len();
-  let next_layout = wm.config.keyboard_layouts[next_index].clone();
+  let next_index = (current_index + 1) % config.keyboard_layouts.len();
+  let next_layout = config.keyboard_layouts[next_index].clone();
   debug!("Switching keyboard layout to: {:?}", &next_layout);
+  let layout = next_layout.clone();
   wm.config_manager.update_config(move |config| {
-    config.keyboard = next_layout;
+    config.keyboard = layout;
+  });
+  drop(config);
+  apply_all_device_configs(wm);
+  wm.broadcast_ipc_event(|| IpcEvent::KeyboardLayoutSwitched {
+    layout: next_layout,
+    device: None,
   });
 }
@@ -1,22 +1,127 @@
+mod easing;
+mod spring;
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
 use std::{
   any::Any,
-  cell::RefCell,
+  cell::{Cell, Ref, RefCell},
   collections::HashMap,
+  error::Error,
   hash::Hash,
   ptr,
   rc::Rc,
-  time::{Duration, SystemTime},
+  time::{Duration, Instant},
 };
 use wlral::{listener, output_manager::OutputManager};
 
+/// Frames closer together than this are taken to be the same refresh on
+/// different outputs, so that animations are stepped once for them
+const SAME_FRAME_INTERVAL: Duration = Duration::from_millis(4);
+
+pub(crate) use easing::Easing;
+pub(crate) use spring::{Spring, SpringConfig};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AnimationsConfig {
+  /// If false, all changes are applied immediately
+  pub(crate) enabled: bool,
+  pub(crate) window_move: AnimationConfig,
+  pub(crate) window_resize: AnimationConfig,
+  /// Slides workspaces vertically when switching workspace on an output.
+  /// Workspaces are switched instantly if unset.
+  pub(crate) workspace_slide: Option<AnimationConfig>,
+}
+
+impl Default for AnimationsConfig {
+  fn default() -> Self {
+    AnimationsConfig {
+      enabled: true,
+      window_move: AnimationConfig::default(),
+      window_resize: AnimationConfig::default(),
+      workspace_slide: None,
+    }
+  }
+}
+
+impl AnimationsConfig {
+  pub(crate) fn validate(config: &Config) -> Result<(), Box<dyn Error>> {
+    let animations = &config.animations;
+    if let Some(ref workspace_slide) = animations.workspace_slide {
+      if workspace_slide.spring.is_some() {
+        return Err("animations.workspace_slide.spring: is not supported".into());
+      }
+    }
+    let workspace_slide = animations.workspace_slide.clone().unwrap_or_default();
+    for (name, animation) in [
+      ("window_move", &animations.window_move),
+      ("window_resize", &animations.window_resize),
+      ("workspace_slide", &workspace_slide),
+    ]
+    .iter()
+    {
+      if let Some(easing) = animation.easing {
+        easing
+          .validate()
+          .map_err(|error| format!("animations.{}.easing.{}", name, error))?;
+      }
+      if let Some(spring) = animation.spring {
+        spring
+          .validate()
+          .map_err(|error| format!("animations.{}.spring.{}", name, error))?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Settings for one kind of animation, unset values use the defaults of that
+/// kind
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AnimationConfig {
+  pub(crate) easing: Option<Easing>,
+  /// Duration in milliseconds. Without it, window moves take longer the
+  /// further the window moves.
+  pub(crate) duration: Option<u64>,
+  /// Animates with spring physics instead of easing over a duration. The
+  /// animation keeps its velocity if the target changes before it is done.
+  pub(crate) spring: Option<SpringConfig>,
+}
+
 pub(crate) enum AnimationConflict {
   NoConflict,
   Replace,
   Ignore,
+  /// The running animation takes over the target of the new animation
+  Retarget,
+}
+
+/// Allows downcasting animation drivers to their concrete type
+pub(crate) trait AsAny {
+  fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: 'static> AsAny for T {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
 }
 
-pub(crate) trait AnimationDriver {
+pub(crate) trait AnimationDriver: AsAny {
   fn step(&self, percent: f64);
+  /// Advances an animation without a fixed duration by the time since the
+  /// last frame. Returns true when it has come to rest.
+  fn step_physics(&self, _dt: Duration) -> bool {
+    true
+  }
+  /// If the end state has taken effect, e.g. when it depends on a client.
+  /// The animation is kept at its end until it has.
+  fn is_settled(&self) -> bool {
+    true
+  }
   fn started(&self) {}
   fn aborted(&self) {}
   fn completed(&self) {}
@@ -26,6 +131,11 @@ pub(crate) trait AnimationDriver {
   {
     AnimationConflict::NoConflict
   }
+  fn retarget(&self, _other: &Self)
+  where
+    Self: Sized,
+  {
+  }
 }
 
 impl AnimationDriver for Box<dyn Fn(f64)> {
@@ -39,13 +149,14 @@ enum AnimationState {
   Waiting,
   Running,
   Completed,
-  Error,
 }
 
 pub(crate) struct Animation<T: ?Sized + AnimationDriver> {
   pub(crate) driver: Box<T>,
   pub(crate) delay: Duration,
-  pub(crate) duration: Duration,
+  /// None for physics based animations, that run until they come to rest
+  pub(crate) duration: Option<Duration>,
+  pub(crate) easing: Easing,
 }
 
 impl<T: AnimationDriver> Animation<T> {
@@ -57,25 +168,34 @@ impl<T: AnimationDriver> Animation<T> {
     Animation {
       driver: Box::new(driver),
       delay,
-      duration,
+      duration: Some(duration),
+      easing: Easing::default(),
+    }
+  }
+
+  pub(crate) fn physics(driver: T) -> Animation<T> {
+    Animation {
+      driver: Box::new(driver),
+      delay: Duration::from_micros(0),
+      duration: None,
+      easing: Easing::default(),
     }
   }
+
+  pub(crate) fn with_easing(self, easing: Easing) -> Animation<T> {
+    Animation { easing, ..self }
+  }
 }
 
 impl<T: ?Sized + AnimationDriver> Animation<T> {
   fn frame(
     &self,
-    now: SystemTime,
-    start_time: SystemTime,
+    now: Instant,
+    start_time: Instant,
+    last_frame: Instant,
     last_state: AnimationState,
   ) -> AnimationState {
-    let elapsed = match now.duration_since(start_time) {
-      Ok(duration) => duration,
-      Err(_) => {
-        self.driver.aborted();
-        return AnimationState::Error;
-      }
-    };
+    let elapsed = now.saturating_duration_since(start_time);
     if elapsed <= self.delay {
       return AnimationState::Waiting;
     }
@@ -83,15 +203,29 @@ impl<T: ?Sized + AnimationDriver> Animation<T> {
       self.driver.started();
     }
 
-    let percent = (elapsed - self.delay).as_micros() as f64 / (self.duration).as_micros() as f64;
+    let duration = match self.duration {
+      Some(duration) => duration,
+      None => {
+        let dt = now.saturating_duration_since(last_frame);
+        if self.driver.step_physics(dt) && self.driver.is_settled() {
+          self.driver.completed();
+          return AnimationState::Completed;
+        }
+        return AnimationState::Running;
+      }
+    };
+    let percent = (elapsed - self.delay).as_micros() as f64 / duration.as_micros() as f64;
 
     if percent >= 1.0 {
       self.driver.step(1.0);
+      if !self.driver.is_settled() {
+        return AnimationState::Running;
+      }
       self.driver.completed();
       return AnimationState::Completed;
     }
 
-    self.driver.step(percent);
+    self.driver.step(self.easing.apply(percent));
     AnimationState::Running
   }
 }
@@ -109,14 +243,23 @@ impl<T: ?Sized + AnimationDriver> Hash for Animation<T> {
 }
 
 pub(crate) struct AnimationManager {
+  config: RefCell<AnimationsConfig>,
+  /// Start time, time of the last frame and state of each animation
   running_animations:
-    RefCell<HashMap<Animation<dyn AnimationDriver>, Option<(SystemTime, AnimationState)>>>,
+    RefCell<HashMap<Animation<dyn AnimationDriver>, Option<(Instant, Instant, AnimationState)>>>,
+  /// When the animations were last stepped, by a frame from any output
+  last_frame: Cell<Option<Instant>>,
 }
 
 impl AnimationManager {
-  pub(crate) fn init(output_manager: Rc<OutputManager>) -> Rc<AnimationManager> {
+  pub(crate) fn init(
+    output_manager: Rc<OutputManager>,
+    config: AnimationsConfig,
+  ) -> Rc<AnimationManager> {
     let animation_manager = Rc::new(AnimationManager {
+      config: RefCell::new(config),
       running_animations: RefCell::new(HashMap::new()),
+      last_frame: Cell::new(None),
     });
     output_manager
       .on_new_output()
@@ -128,10 +271,45 @@ impl AnimationManager {
     animation_manager
   }
 
+  pub(crate) fn config(&self) -> Ref<AnimationsConfig> {
+    self.config.borrow()
+  }
+
+  pub(crate) fn set_config(&self, config: AnimationsConfig) {
+    *self.config.borrow_mut() = config;
+  }
+
+  /// Finds the first running animation with a driver of type T that f
+  /// returns a value for
+  pub(crate) fn find_running<T: 'static + AnimationDriver, R>(
+    &self,
+    f: impl Fn(&T) -> Option<R>,
+  ) -> Option<R> {
+    self
+      .running_animations
+      .borrow()
+      .keys()
+      .filter_map(|animation| animation.driver.as_ref().as_any().downcast_ref::<T>())
+      .find_map(f)
+  }
+
+  /// Aborts the running animations with a driver of type T that f matches
+  pub(crate) fn abort_running<T: 'static + AnimationDriver>(&self, f: impl Fn(&T) -> bool) {
+    self.running_animations.borrow_mut().retain(|animation, _| {
+      match animation.driver.as_ref().as_any().downcast_ref::<T>() {
+        Some(driver) if f(driver) => {
+          driver.aborted();
+          false
+        }
+        _ => true,
+      }
+    });
+  }
+
   pub(crate) fn start<T: 'static + AnimationDriver>(&self, animation: Animation<T>) {
     let mut ignore = false;
     self.running_animations.borrow_mut().retain(|old, _| {
-      if let Some(old_driver) = Any::downcast_ref::<T>(&old.driver) {
+      if let Some(old_driver) = old.driver.as_ref().as_any().downcast_ref::<T>() {
         match animation.driver.is_conflict(old_driver) {
           AnimationConflict::NoConflict => true,
           AnimationConflict::Replace => {
@@ -142,6 +320,11 @@ impl AnimationManager {
             ignore = true;
             true
           }
+          AnimationConflict::Retarget => {
+            old_driver.retarget(&animation.driver);
+            ignore = true;
+            true
+          }
         }
       } else {
         true
@@ -149,31 +332,48 @@ impl AnimationManager {
     });
 
     if !ignore {
+      // Disabled animations still run, but complete on the next frame
+      let (delay, duration) = if self.config().enabled {
+        (animation.delay, animation.duration)
+      } else {
+        (Duration::from_micros(0), Some(Duration::from_micros(0)))
+      };
       self.running_animations.borrow_mut().insert(
         Animation {
           driver: animation.driver as Box<dyn AnimationDriver>,
-          delay: animation.delay,
-          duration: animation.duration,
+          delay,
+          duration,
+          easing: animation.easing,
         },
         None,
       );
     }
   }
 
+  /// Steps the animations on a frame from any output, so that they keep
+  /// running as long as some output is on
   fn frame(&self) {
-    let now = SystemTime::now();
+    let now = Instant::now();
+    let same_frame = self.last_frame.get().map_or(false, |last_frame| {
+      now.saturating_duration_since(last_frame) < SAME_FRAME_INTERVAL
+    });
+    if same_frame {
+      return;
+    }
+    self.last_frame.set(Some(now));
+
     self
       .running_animations
       .borrow_mut()
       .retain(|animation, animation_state| {
-        let (start_time, last_state) =
-          animation_state.get_or_insert((now, AnimationState::Waiting));
+        let (start_time, last_frame, last_state) =
+          animation_state.get_or_insert((now, now, AnimationState::Waiting));
         let start_time = *start_time;
-        let state = animation.frame(now, start_time, *last_state);
+        let state = animation.frame(now, start_time, *last_frame, *last_state);
         match state {
-          AnimationState::Completed | AnimationState::Error => false,
+          AnimationState::Completed => false,
           _ => {
-            animation_state.replace((start_time, state));
+            animation_state.replace((start_time, now, state));
             true
           }
         }
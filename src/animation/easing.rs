@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+/// Maps the linear progress of an animation to the progress of the animated
+/// value
+#[derive(Default, Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Easing {
+  #[default]
+  Linear,
+  EaseInQuad,
+  EaseOutQuad,
+  EaseInOutQuad,
+  EaseInCubic,
+  EaseOutCubic,
+  EaseInOutCubic,
+  EaseOutExpo,
+  /// Control points x1, y1, x2, y2 like CSS cubic-bezier()
+  CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+  pub(crate) fn validate(&self) -> Result<(), String> {
+    if let Easing::CubicBezier(x1, _, x2, _) = *self {
+      // Otherwise the curve could go back in time
+      if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+        return Err(format!(
+          "cubic_bezier: x1 and x2 must be between 0 and 1, was {} and {}",
+          x1, x2
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  pub(crate) fn apply(&self, t: f64) -> f64 {
+    if t <= 0.0 {
+      return 0.0;
+    }
+    if t >= 1.0 {
+      return 1.0;
+    }
+    match *self {
+      Easing::Linear => t,
+      Easing::EaseInQuad => t * t,
+      Easing::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+      Easing::EaseInOutQuad => {
+        if t < 0.5 {
+          2.0 * t * t
+        } else {
+          1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+        }
+      }
+      Easing::EaseInCubic => t * t * t,
+      Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+      Easing::EaseInOutCubic => {
+        if t < 0.5 {
+          4.0 * t * t * t
+        } else {
+          1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+        }
+      }
+      Easing::EaseOutExpo => 1.0 - 2f64.powf(-10.0 * t),
+      Easing::CubicBezier(x1, y1, x2, y2) => {
+        let s = solve_bezier(t, x1, x2);
+        bezier(s, y1, y2)
+      }
+    }
+  }
+}
+
+/// One dimension of a cubic bezier from 0 to 1 with the control points p1 and p2
+fn bezier(s: f64, p1: f64, p2: f64) -> f64 {
+  let inv = 1.0 - s;
+  3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+}
+
+fn bezier_derivative(s: f64, p1: f64, p2: f64) -> f64 {
+  let inv = 1.0 - s;
+  3.0 * inv * inv * p1 + 6.0 * inv * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
+
+/// Finds the curve parameter where the x coordinate is x
+fn solve_bezier(x: f64, x1: f64, x2: f64) -> f64 {
+  const EPSILON: f64 = 1e-6;
+
+  // Newton's method converges quickly for most curves
+  let mut s = x;
+  for _ in 0..8 {
+    let error = bezier(s, x1, x2) - x;
+    if error.abs() < EPSILON {
+      return s;
+    }
+    let derivative = bezier_derivative(s, x1, x2);
+    if derivative.abs() < EPSILON {
+      break;
+    }
+    s -= error / derivative;
+  }
+
+  // Fall back to bisection for flat parts of the curve
+  let mut low = 0.0;
+  let mut high = 1.0;
+  s = x;
+  while high - low > EPSILON {
+    if bezier(s, x1, x2) < x {
+      low = s;
+    } else {
+      high = s;
+    }
+    s = (low + high) / 2.0;
+  }
+  s
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EASINGS: [Easing; 11] = [
+    Easing::Linear,
+    Easing::EaseInQuad,
+    Easing::EaseOutQuad,
+    Easing::EaseInOutQuad,
+    Easing::EaseInCubic,
+    Easing::EaseOutCubic,
+    Easing::EaseInOutCubic,
+    Easing::EaseOutExpo,
+    Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    Easing::CubicBezier(0.0, 0.0, 1.0, 1.0),
+    Easing::CubicBezier(1.0, 0.0, 0.0, 1.0),
+  ];
+
+  #[test]
+  fn easings_start_at_0_and_end_at_1() {
+    for easing in EASINGS.iter() {
+      assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+      assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+      assert!(easing.apply(0.001).abs() < 0.01, "{:?}", easing);
+      assert!((easing.apply(0.999) - 1.0).abs() < 0.01, "{:?}", easing);
+    }
+  }
+
+  #[test]
+  fn cubic_bezier_with_valid_control_points_is_monotonic() {
+    let control_points = [0.0, 0.1, 0.42, 0.58, 0.9, 1.0];
+    for x1 in control_points.iter() {
+      for x2 in control_points.iter() {
+        for y1 in control_points.iter() {
+          for y2 in control_points.iter() {
+            let easing = Easing::CubicBezier(*x1, *y1, *x2, *y2);
+            assert!(easing.validate().is_ok());
+            let mut previous = 0.0;
+            for i in 0..=100 {
+              let value = easing.apply(i as f64 / 100.0);
+              assert!(value >= previous - 1e-6, "{:?} at {}", easing, i);
+              previous = value;
+            }
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn cubic_bezier_is_solved_for_x() {
+    for (x1, x2) in [(0.25, 0.25), (0.0, 1.0), (1.0, 0.0), (0.9, 0.1)].iter() {
+      for i in 0..=100 {
+        let x = i as f64 / 100.0;
+        let s = solve_bezier(x, *x1, *x2);
+        assert!((bezier(s, *x1, *x2) - x).abs() < 1e-4, "x: {}", x);
+      }
+    }
+  }
+
+  #[test]
+  fn control_points_outside_of_the_time_range_are_rejected() {
+    assert!(Easing::CubicBezier(-0.1, 0.0, 0.5, 1.0).validate().is_err());
+    assert!(Easing::CubicBezier(0.5, 0.0, 1.1, 1.0).validate().is_err());
+    assert!(Easing::CubicBezier(0.5, -1.0, 0.5, 2.0).validate().is_ok());
+  }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Longest time simulated in one integration step, to keep stiff springs stable
+const MAX_TIME_STEP: Duration = Duration::from_millis(1);
+/// Longest time simulated in one call to step, so that a long pause between
+/// frames does not make the spring jump
+const MAX_STEP: Duration = Duration::from_millis(100);
+/// Distance from the target below which the spring can be at rest
+const REST_DISTANCE: f64 = 0.5;
+/// Velocity, per second, below which the spring can be at rest
+const REST_VELOCITY: f64 = 5.0;
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SpringConfig {
+  pub(crate) stiffness: f64,
+  /// 1.0 is critically damped, lower values overshoot the target
+  pub(crate) damping_ratio: f64,
+  pub(crate) mass: f64,
+}
+
+impl Default for SpringConfig {
+  fn default() -> Self {
+    SpringConfig {
+      stiffness: 400.0,
+      damping_ratio: 1.0,
+      mass: 1.0,
+    }
+  }
+}
+
+impl SpringConfig {
+  pub(crate) fn validate(&self) -> Result<(), String> {
+    if self.stiffness <= 0.0 {
+      return Err("stiffness: must be positive".to_string());
+    }
+    if self.damping_ratio <= 0.0 {
+      return Err("damping_ratio: must be positive".to_string());
+    }
+    if self.mass <= 0.0 {
+      return Err("mass: must be positive".to_string());
+    }
+    Ok(())
+  }
+
+  fn damping(&self) -> f64 {
+    2.0 * self.damping_ratio * (self.stiffness * self.mass).sqrt()
+  }
+}
+
+/// A damped spring in one dimension, that keeps its velocity when the target
+/// changes
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Spring {
+  config: SpringConfig,
+  pub(crate) position: f64,
+  pub(crate) velocity: f64,
+  pub(crate) target: f64,
+}
+
+impl Spring {
+  pub(crate) fn new(config: SpringConfig, position: f64, target: f64) -> Spring {
+    Spring {
+      config,
+      position,
+      velocity: 0.0,
+      target,
+    }
+  }
+
+  pub(crate) fn step(&mut self, dt: Duration) {
+    let damping = self.config.damping();
+    let mut remaining = dt.min(MAX_STEP);
+    while remaining > Duration::from_micros(0) {
+      let time_step = remaining.min(MAX_TIME_STEP);
+      remaining -= time_step;
+      let dt = time_step.as_secs_f64();
+
+      let force = -self.config.stiffness * (self.position - self.target) - damping * self.velocity;
+      self.velocity += force / self.config.mass * dt;
+      self.position += self.velocity * dt;
+    }
+  }
+
+  pub(crate) fn is_at_rest(&self) -> bool {
+    (self.position - self.target).abs() < REST_DISTANCE && self.velocity.abs() < REST_VELOCITY
+  }
+
+  pub(crate) fn finish(&mut self) {
+    self.position = self.target;
+    self.velocity = 0.0;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settle(spring: &mut Spring) {
+    let frame = Duration::from_millis(16);
+    let mut elapsed = Duration::from_millis(0);
+    while !spring.is_at_rest() {
+      assert!(
+        elapsed < Duration::from_secs(10),
+        "{:?} never came to rest",
+        spring
+      );
+      spring.step(frame);
+      elapsed += frame;
+    }
+  }
+
+  #[test]
+  fn spring_comes_to_rest_at_its_target() {
+    let configs = [
+      SpringConfig::default(),
+      SpringConfig {
+        damping_ratio: 0.3,
+        ..SpringConfig::default()
+      },
+      SpringConfig {
+        stiffness: 2000.0,
+        damping_ratio: 2.0,
+        mass: 0.5,
+      },
+    ];
+    for config in configs.iter() {
+      let mut spring = Spring::new(*config, 0.0, 1000.0);
+      settle(&mut spring);
+      assert!((spring.position - 1000.0).abs() < REST_DISTANCE);
+    }
+  }
+
+  #[test]
+  fn spring_keeps_going_to_a_changed_target() {
+    let mut spring = Spring::new(SpringConfig::default(), 0.0, 1000.0);
+    spring.step(Duration::from_millis(50));
+    assert!(spring.velocity > 0.0);
+    spring.target = -500.0;
+    settle(&mut spring);
+    assert!((spring.position + 500.0).abs() < REST_DISTANCE);
+  }
+
+  #[test]
+  fn long_steps_are_clamped() {
+    let mut clamped = Spring::new(SpringConfig::default(), 0.0, 1000.0);
+    clamped.step(Duration::from_secs(60));
+    let mut expected = Spring::new(SpringConfig::default(), 0.0, 1000.0);
+    expected.step(MAX_STEP);
+    assert_eq!(clamped.position, expected.position);
+    assert_eq!(clamped.velocity, expected.velocity);
+  }
+}
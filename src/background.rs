@@ -2,7 +2,12 @@ use crate::config::Config;
 use log::error;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fs, process::Command, rc::Rc};
+use std::{
+  error::Error,
+  fs,
+  process::{Child, Command},
+  rc::Rc,
+};
 use wlral::config::ConfigManager;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
@@ -80,7 +85,11 @@ impl BackgroundConfig {
     Ok(())
   }
 
-  pub(crate) fn init(config: &Config, config_manager: Rc<ConfigManager>) {
+  /// Applies the background color and starts swaybg if an image is configured.
+  ///
+  /// The returned swaybg process should be killed before the background is
+  /// applied again.
+  pub(crate) fn init(config: &Config, config_manager: Rc<ConfigManager>) -> Option<Child> {
     config_manager.update_config(|c| c.background_color = config.background.parsed_color);
     if let Some(ref image) = config.background.image {
       let result = Command::new("swaybg")
@@ -88,9 +97,15 @@ impl BackgroundConfig {
         .args(&["-m", &config.background.image_mode.to_string()])
         .spawn();
 
-      if let Err(error) = result {
-        error!("swaybg failed to start: {}", error);
+      match result {
+        Ok(swaybg) => Some(swaybg),
+        Err(error) => {
+          error!("swaybg failed to start: {}", error);
+          None
+        }
       }
+    } else {
+      None
     }
   }
 }
@@ -0,0 +1,201 @@
+#[path = "../ipc/protocol.rs"]
+mod protocol;
+#[path = "../shortcuts.rs"]
+mod shortcuts;
+
+use protocol::*;
+use serde_yaml::{Mapping, Value};
+use shortcuts::ActionShortcut;
+use std::{
+  env,
+  error::Error,
+  io::{BufRead, BufReader, Write},
+  os::unix::net::UnixStream,
+  process,
+};
+
+const USAGE: &str = "Usage: cascadectl <command>
+
+Commands:
+  action <action> [--<argument> <value>]...
+      Triggers an action, using the same names and arguments as the
+      keyboard_shortcuts config, e.g. `action navigate --direction left`
+  get <outputs|workspaces|windows|focused-window|keyboard-layout|keyboard-mode> [--json]
+      Prints the current state of the window manager
+  subscribe
+      Prints events as lines of JSON until interrupted
+  reload
+      Reloads the config file";
+
+fn main() {
+  let args = env::args().skip(1).collect::<Vec<_>>();
+  if let Err(error) = run(&args) {
+    eprintln!("cascadectl: {}", error);
+    process::exit(1);
+  }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+  let command = args.first().map(String::as_str);
+  match command {
+    Some("action") => {
+      let action = parse_action(&args[1..])?;
+      expect_success(request(&IpcRequest::Action(action))?)
+    }
+    Some("reload") => expect_success(request(&IpcRequest::Action(ActionShortcut::ReloadConfig))?),
+    Some("get") => {
+      let json = args.iter().any(|arg| arg == "--json");
+      let query = match args.get(1).map(String::as_str) {
+        Some("outputs") => IpcRequest::GetOutputs,
+        Some("workspaces") => IpcRequest::GetWorkspaces,
+        Some("windows") => IpcRequest::GetWindows,
+        Some("focused-window") => IpcRequest::GetFocusedWindow,
+        Some("keyboard-layout") => IpcRequest::GetKeyboardLayout,
+        Some("keyboard-mode") => IpcRequest::GetKeyboardMode,
+        Some(other) => return Err(format!("Unknown query \"{}\"\n\n{}", other, USAGE).into()),
+        None => return Err(USAGE.into()),
+      };
+      let response = request(&query)?;
+      if json {
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        Ok(())
+      } else {
+        print_response(response)
+      }
+    }
+    Some("subscribe") => subscribe(),
+    Some("help") | Some("--help") | Some("-h") => {
+      println!("{}", USAGE);
+      Ok(())
+    }
+    Some(other) => Err(format!("Unknown command \"{}\"\n\n{}", other, USAGE).into()),
+    None => Err(USAGE.into()),
+  }
+}
+
+/// Builds an action from the command line the same way it would be read from
+/// the keyboard_shortcuts config, so both accept the same names and values.
+fn parse_action(args: &[String]) -> Result<ActionShortcut, Box<dyn Error>> {
+  let name = args.first().ok_or("No action specified")?;
+  let mut action = Mapping::new();
+  action.insert(
+    Value::String("action".to_string()),
+    Value::String(name.replace('-', "_")),
+  );
+
+  let mut args = args[1..].iter();
+  while let Some(arg) = args.next() {
+    let key = match arg.strip_prefix("--") {
+      Some(key) => key,
+      None => return Err(format!("Expected an argument starting with --, got \"{}\"", arg).into()),
+    };
+    let value = args
+      .next()
+      .ok_or_else(|| format!("Missing value for argument \"{}\"", arg))?;
+    action.insert(
+      Value::String(key.replace('-', "_")),
+      serde_yaml::from_str(value)?,
+    );
+  }
+
+  Ok(serde_yaml::from_value(Value::Mapping(action))?)
+}
+
+fn connect() -> Result<UnixStream, Box<dyn Error>> {
+  let socket_path =
+    env::var(SOCKET_ENV).map_err(|_| format!("{} is not set, is Cascade running?", SOCKET_ENV))?;
+  UnixStream::connect(&socket_path)
+    .map_err(|error| format!("Could not connect to \"{}\": {}", socket_path, error).into())
+}
+
+fn send(stream: &mut UnixStream, request: &IpcRequest) -> Result<(), Box<dyn Error>> {
+  serde_json::to_writer(&mut *stream, request)?;
+  stream.write_all(b"\n")?;
+  Ok(())
+}
+
+fn request(request: &IpcRequest) -> Result<IpcResponse, Box<dyn Error>> {
+  let mut stream = connect()?;
+  send(&mut stream, request)?;
+  let mut line = String::new();
+  BufReader::new(stream).read_line(&mut line)?;
+  Ok(serde_json::from_str(&line)?)
+}
+
+fn expect_success(response: IpcResponse) -> Result<(), Box<dyn Error>> {
+  match response {
+    IpcResponse::Success => Ok(()),
+    IpcResponse::Error(error) => Err(error.into()),
+    other => Err(format!("Unexpected response: {:?}", other).into()),
+  }
+}
+
+fn subscribe() -> Result<(), Box<dyn Error>> {
+  let mut stream = connect()?;
+  send(&mut stream, &IpcRequest::Subscribe)?;
+  let mut lines = BufReader::new(stream).lines();
+  let response = lines.next().ok_or("Connection closed")??;
+  expect_success(serde_json::from_str(&response)?)?;
+  for line in lines {
+    println!("{}", line?);
+  }
+  Ok(())
+}
+
+fn print_window(window: &WindowInfo) {
+  println!(
+    "{}{:<16} {:<24} {}",
+    if window.focused { "*" } else { " " },
+    window.id,
+    window.app_id.as_deref().unwrap_or("-"),
+    window.title.as_deref().unwrap_or("-"),
+  );
+}
+
+fn print_response(response: IpcResponse) -> Result<(), Box<dyn Error>> {
+  match response {
+    IpcResponse::Outputs(outputs) => {
+      for output in outputs {
+        println!(
+          "{}{:<16} {}x{} at {},{}",
+          if output.focused { "*" } else { " " },
+          output.name,
+          output.width,
+          output.height,
+          output.x,
+          output.y,
+        );
+      }
+    }
+    IpcResponse::Workspaces(workspaces) => {
+      for workspace in workspaces {
+        println!(
+          "{}{:<4} {:<16} {:<16} {} windows",
+          if workspace.focused { "*" } else { " " },
+          workspace.index,
+          workspace.name.as_deref().unwrap_or("-"),
+          workspace.output.as_deref().unwrap_or("-"),
+          workspace.windows.len(),
+        );
+      }
+    }
+    IpcResponse::Windows(windows) => {
+      for window in windows.iter() {
+        print_window(window);
+      }
+    }
+    IpcResponse::FocusedWindow(window) => {
+      if let Some(ref window) = window {
+        print_window(window);
+      }
+    }
+    IpcResponse::KeyboardLayout(layout) => {
+      println!("{}", serde_yaml::to_string(&layout)?);
+    }
+    IpcResponse::KeyboardMode(mode) => {
+      println!("{}", mode.as_deref().unwrap_or("default"));
+    }
+    other => expect_success(other)?,
+  }
+  Ok(())
+}
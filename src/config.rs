@@ -1,21 +1,47 @@
-use crate::{background::BackgroundConfig, keyboard::KeyboardShortcutsConfig};
+use crate::{
+  animation::AnimationsConfig,
+  background::BackgroundConfig,
+  gaps::GapsConfig,
+  keyboard::{KeyboardShortcutsConfig, Modifier},
+  keyboard_devices::KeyboardDeviceConfig,
+  shortcuts::WorkspaceSelector,
+  window_rules::WindowRule,
+};
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fs};
+use std::{collections::BTreeMap, error::Error, fs};
 use wlral::input::keyboard::KeyboardConfig;
 
 #[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub(crate) struct Config {
+  pub(crate) animations: AnimationsConfig,
   pub(crate) background: BackgroundConfig,
+  pub(crate) gaps: GapsConfig,
   pub(crate) keyboard_layouts: Vec<KeyboardConfig>,
+  pub(crate) keyboard_devices: Vec<KeyboardDeviceConfig>,
   pub(crate) keyboard_shortcuts: KeyboardShortcutsConfig,
+  /// Named sets of keybindings that replace keyboard_shortcuts while active
+  pub(crate) keyboard_modes: BTreeMap<String, KeyboardShortcutsConfig>,
+  /// Milliseconds to wait for the next key of a sequence before it is reset
+  pub(crate) key_sequence_timeout: Option<u64>,
+  /// Milliseconds a key must be held to trigger hold instead of tap shortcuts
+  pub(crate) key_hold_timeout: Option<u64>,
+  /// Modifiers that are not considered when matching shortcuts, defaults to
+  /// caps_lock and num_lock
+  pub(crate) ignored_modifiers: Option<Vec<Modifier>>,
   pub(crate) extra_workspaces: usize,
+  /// Names for workspaces, by index
+  pub(crate) workspace_names: BTreeMap<usize, String>,
+  pub(crate) window_rules: Vec<WindowRule>,
 }
 
 impl Config {
+  pub(crate) fn path() -> String {
+    shellexpand::tilde("~/.config/cascade/config.yaml").to_string()
+  }
+
   pub(crate) fn load() -> Result<Config, Box<dyn Error>> {
-    let config_string =
-      fs::read_to_string(shellexpand::tilde("~/.config/cascade/config.yaml").to_string())?;
+    let config_string = fs::read_to_string(Config::path())?;
     let mut config: Config = serde_yaml::from_str(&config_string)?;
 
     for (i, a) in config.keyboard_layouts.iter().enumerate() {
@@ -32,8 +58,53 @@ impl Config {
       }
     }
 
+    for (index, name) in config.workspace_names.iter() {
+      if *index == 0 {
+        return Err("workspace_names: indices start at 1".into());
+      }
+      if name.is_empty() {
+        return Err(format!("workspace_names.{}: name must not be empty", index).into());
+      }
+      if let Some(other) = config
+        .workspace_names
+        .iter()
+        .find(|(other, other_name)| *other != index && *other_name == name)
+        .map(|(other, _)| other)
+      {
+        return Err(
+          format!(
+            "workspace_names.{}: \"{}\" is also used by workspace {}",
+            index, name, other
+          )
+          .into(),
+        );
+      }
+    }
+
+    KeyboardDeviceConfig::validate(&config)?;
+    KeyboardShortcutsConfig::validate(&config)?;
     BackgroundConfig::validate(&mut config)?;
+    GapsConfig::validate(&config)?;
+    AnimationsConfig::validate(&config)?;
+    WindowRule::validate(&config)?;
 
     Ok(config)
   }
+
+  pub(crate) fn workspace_name(&self, index: usize) -> Option<&str> {
+    self.workspace_names.get(&index).map(String::as_str)
+  }
+
+  /// Resolves a workspace selector to a workspace index
+  pub(crate) fn workspace_index(&self, selector: &WorkspaceSelector) -> Option<usize> {
+    match selector {
+      WorkspaceSelector::Index(0) => None,
+      WorkspaceSelector::Index(index) => Some(*index),
+      WorkspaceSelector::Name(name) => self
+        .workspace_names
+        .iter()
+        .find(|(_, workspace_name)| *workspace_name == name)
+        .map(|(index, _)| *index),
+    }
+  }
 }
@@ -1,9 +1,16 @@
+pub(crate) mod column;
 pub(crate) mod mru_list;
+pub(crate) mod scratchpad;
 pub(crate) mod window;
+pub(crate) mod window_cycle;
 pub(crate) mod workspace;
+pub(crate) mod workspace_slide;
 
+pub(crate) use column::Column;
 pub(crate) use mru_list::MruList;
+pub(crate) use scratchpad::Scratchpad;
 use std::rc::Rc;
+pub(crate) use window_cycle::WindowCycle;
 use wlral::geometry::*;
 use wlral::window::Window;
 use wlral::window_management_policy::*;
@@ -0,0 +1,128 @@
+use std::rc::Rc;
+use wlral::window::Window;
+
+/// Windows stacked vertically in a single column of the strip.
+#[derive(Debug)]
+pub(crate) struct Column {
+  windows: Vec<Rc<Window>>,
+  /// Fraction of the output height given to each window, summing to 1
+  heights: Vec<f32>,
+}
+
+impl Column {
+  pub(crate) fn new(window: Rc<Window>) -> Column {
+    Column {
+      windows: vec![window],
+      heights: vec![1.0],
+    }
+  }
+
+  pub(crate) fn windows(&self) -> &[Rc<Window>] {
+    &self.windows
+  }
+  pub(crate) fn heights(&self) -> &[f32] {
+    &self.heights
+  }
+  pub(crate) fn len(&self) -> usize {
+    self.windows.len()
+  }
+  pub(crate) fn is_empty(&self) -> bool {
+    self.windows.is_empty()
+  }
+
+  pub(crate) fn contains(&self, window: &Window) -> bool {
+    self.windows.iter().any(|w| w.as_ref() == window)
+  }
+  pub(crate) fn index_of_window(&self, window: &Window) -> Option<usize> {
+    self.windows.iter().position(|w| w.as_ref() == window)
+  }
+
+  /// Inserts a window, giving it an equal share of the height while keeping
+  /// the relative heights of the other windows.
+  pub(crate) fn insert(&mut self, index: usize, window: Rc<Window>) {
+    self.windows.insert(index, window);
+    insert_height(&mut self.heights, index);
+  }
+  pub(crate) fn push(&mut self, window: Rc<Window>) {
+    self.insert(self.windows.len(), window);
+  }
+
+  /// Removes a window, giving its height to the remaining windows.
+  pub(crate) fn remove(&mut self, window: &Window) -> Option<Rc<Window>> {
+    let index = self.index_of_window(window)?;
+    remove_height(&mut self.heights, index);
+    Some(self.windows.remove(index))
+  }
+
+  pub(crate) fn swap(&mut self, a: usize, b: usize) {
+    self.windows.swap(a, b);
+    self.heights.swap(a, b);
+  }
+}
+
+fn insert_height(heights: &mut Vec<f32>, index: usize) {
+  let share = 1.0 / (heights.len() + 1) as f32;
+  for height in heights.iter_mut() {
+    *height *= 1.0 - share;
+  }
+  heights.insert(index, share);
+}
+
+fn remove_height(heights: &mut Vec<f32>, index: usize) {
+  let removed_height = heights.remove(index);
+  if removed_height < 1.0 {
+    for height in heights.iter_mut() {
+      *height /= 1.0 - removed_height;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_heights(heights: &[f32], expected: &[f32]) {
+    assert_eq!(heights.len(), expected.len(), "{:?}", heights);
+    for (height, expected) in heights.iter().zip(expected) {
+      assert!((height - expected).abs() < 1e-6, "{:?}", heights);
+    }
+    if !heights.is_empty() {
+      assert!((heights.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    }
+  }
+
+  #[test]
+  fn pulled_windows_get_an_equal_share() {
+    let mut heights = vec![1.0];
+    insert_height(&mut heights, 1);
+    assert_heights(&heights, &[0.5, 0.5]);
+    insert_height(&mut heights, 0);
+    assert_heights(&heights, &[1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0]);
+  }
+
+  #[test]
+  fn pulled_windows_keep_the_relative_heights_of_the_others() {
+    let mut heights = vec![0.75, 0.25];
+    insert_height(&mut heights, 1);
+    assert_heights(&heights, &[0.5, 1.0 / 3.0, 1.0 / 6.0]);
+  }
+
+  #[test]
+  fn expelled_windows_give_their_height_to_the_others() {
+    let mut heights = vec![0.5, 0.3, 0.2];
+    remove_height(&mut heights, 0);
+    assert_heights(&heights, &[0.6, 0.4]);
+    remove_height(&mut heights, 1);
+    assert_heights(&heights, &[1.0]);
+    remove_height(&mut heights, 0);
+    assert_heights(&heights, &[]);
+  }
+
+  #[test]
+  fn pulling_and_expelling_restores_the_heights() {
+    let mut heights = vec![0.7, 0.3];
+    insert_height(&mut heights, 2);
+    remove_height(&mut heights, 2);
+    assert_heights(&heights, &[0.7, 0.3]);
+  }
+}
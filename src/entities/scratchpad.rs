@@ -0,0 +1,64 @@
+use std::{
+  cell::{Ref, RefCell},
+  rc::Rc,
+};
+use wlral::window::Window;
+
+#[derive(Debug)]
+pub(crate) struct ScratchpadWindow {
+  pub(crate) window: Rc<Window>,
+  pub(crate) name: Option<String>,
+}
+
+/// Windows that have been put aside, and can be shown floating on any
+/// workspace.
+///
+/// Hidden windows are not part of any workspace. Shown windows stay in the
+/// scratchpad while they float on a workspace, so that they can be hidden
+/// again.
+#[derive(Debug, Default)]
+pub(crate) struct Scratchpad {
+  windows: RefCell<Vec<ScratchpadWindow>>,
+}
+
+impl Scratchpad {
+  pub(crate) fn windows(&self) -> Ref<Vec<ScratchpadWindow>> {
+    self.windows.borrow()
+  }
+
+  pub(crate) fn contains(&self, window: &Window) -> bool {
+    self
+      .windows
+      .borrow()
+      .iter()
+      .any(|w| w.window.as_ref() == window)
+  }
+
+  pub(crate) fn add(&self, window: Rc<Window>, name: Option<String>) {
+    self.remove(&window);
+    self
+      .windows
+      .borrow_mut()
+      .push(ScratchpadWindow { window, name });
+  }
+
+  pub(crate) fn remove(&self, window: &Window) {
+    self
+      .windows
+      .borrow_mut()
+      .retain(|w| w.window.as_ref() != window);
+  }
+
+  /// The most recently added window that matches both name and app_id, if specified
+  pub(crate) fn find(&self, name: Option<&str>, app_id: Option<&str>) -> Option<Rc<Window>> {
+    self
+      .windows
+      .borrow()
+      .iter()
+      .rev()
+      .filter(|w| name.map_or(true, |name| w.name.as_deref() == Some(name)))
+      .filter(|w| app_id.map_or(true, |app_id| w.window.app_id().as_deref() == Some(app_id)))
+      .map(|w| w.window.clone())
+      .next()
+  }
+}
@@ -1,16 +1,35 @@
-use crate::animation::{Animation, AnimationConflict, AnimationDriver, AnimationManager};
-use std::{rc::Rc, time::Duration};
+use crate::animation::{
+  Animation, AnimationConflict, AnimationDriver, AnimationManager, Easing, Spring, SpringConfig,
+};
+use std::{
+  cell::{Cell, RefCell},
+  rc::Rc,
+  time::{Duration, Instant},
+};
 use wlral::{
-  geometry::{Displacement, FPoint, Point},
+  geometry::{Displacement, FPoint, Point, Size},
   window::Window,
 };
 
 const WINDOW_ANIMATION_SPEED: f64 = 15.0;
+/// The longest window move when no duration is configured
 const MAX_WINDOW_ANIMATION_DURATION_MS: u64 = 300;
+const DEFAULT_WINDOW_RESIZE_DURATION_MS: u64 = 200;
+/// How long a resize waits for the client to commit the requested size
+const RESIZE_COMMIT_TIMEOUT: Duration = Duration::from_millis(500);
 
 pub(crate) trait WindowAnimations {
   fn set_window_position(&self, window: Rc<Window>, to_top_left: Point);
   fn animate_window_position(&self, window: Rc<Window>, to_top_left: Point);
+  /// Stops a running move of the window, so that it can be translated by
+  /// something else
+  fn stop_window_position(&self, window: &Window);
+  fn set_window_size(&self, window: Rc<Window>, to_size: Size);
+  /// Asks the client for to_size once, and scales the window as it is drawn
+  /// from its current size until the animation is done
+  fn animate_window_size(&self, window: Rc<Window>, to_size: Size);
+  /// The size a running resize animation is going to, or the current size
+  fn target_window_size(&self, window: &Window) -> Size;
 }
 
 impl WindowAnimations for AnimationManager {
@@ -19,7 +38,7 @@ impl WindowAnimations for AnimationManager {
     let end: FPoint = to_top_left.into();
     self.start(Animation::immediate(
       Duration::from_millis(0),
-      WindowAnimationDriver { window, start, end },
+      WindowAnimationDriver::tween(window, start, end),
     ));
   }
 
@@ -29,44 +48,356 @@ impl WindowAnimations for AnimationManager {
     if start == end {
       return;
     }
-    let distance = (start - end).length();
+    let config = self.config().window_move.clone();
+    if let Some(spring) = config.spring {
+      self.start(Animation::physics(WindowAnimationDriver::spring(
+        window, start, end, spring,
+      )));
+      return;
+    }
+
+    let duration = config.duration.unwrap_or_else(|| {
+      let distance = (start - end).length();
+      ((distance * WINDOW_ANIMATION_SPEED) as u64).min(MAX_WINDOW_ANIMATION_DURATION_MS)
+    });
+    self.start(
+      Animation::immediate(
+        Duration::from_millis(duration),
+        WindowAnimationDriver::tween(window, start, end),
+      )
+      .with_easing(config.easing.unwrap_or_default()),
+    );
+  }
+
+  fn stop_window_position(&self, window: &Window) {
+    self.abort_running(|driver: &WindowAnimationDriver| driver.window.as_ref() == window);
+  }
+
+  fn set_window_size(&self, window: Rc<Window>, to_size: Size) {
+    let start = displayed_window_size(self, &window);
     self.start(Animation::immediate(
-      Duration::from_millis(
-        ((distance * WINDOW_ANIMATION_SPEED) as u64).min(MAX_WINDOW_ANIMATION_DURATION_MS),
-      ),
-      WindowAnimationDriver { window, start, end },
+      Duration::from_millis(0),
+      WindowSizeAnimationDriver::tween(window, start, to_size),
     ));
   }
+
+  fn animate_window_size(&self, window: Rc<Window>, to_size: Size) {
+    if self.target_window_size(&window) == to_size {
+      return;
+    }
+    let start = displayed_window_size(self, &window);
+    let config = self.config().window_resize.clone();
+    if let Some(spring) = config.spring {
+      self.start(Animation::physics(WindowSizeAnimationDriver::spring(
+        window, start, to_size, spring,
+      )));
+      return;
+    }
+
+    self.start(
+      Animation::immediate(
+        Duration::from_millis(config.duration.unwrap_or(DEFAULT_WINDOW_RESIZE_DURATION_MS)),
+        WindowSizeAnimationDriver::tween(window, start, to_size),
+      )
+      .with_easing(config.easing.unwrap_or(Easing::EaseOutCubic)),
+    );
+  }
+
+  fn target_window_size(&self, window: &Window) -> Size {
+    self
+      .find_running(|driver: &WindowSizeAnimationDriver| {
+        if driver.window.as_ref() == window {
+          Some(driver.end.get())
+        } else {
+          None
+        }
+      })
+      .unwrap_or_else(|| window.size())
+  }
+}
+
+/// The size the window is drawn at by a running resize animation, so that a
+/// new animation continues from there
+fn displayed_window_size(animation_manager: &AnimationManager, window: &Window) -> (f64, f64) {
+  animation_manager
+    .find_running(|driver: &WindowSizeAnimationDriver| {
+      if driver.window.as_ref() == window {
+        Some(driver.displayed.get())
+      } else {
+        None
+      }
+    })
+    .unwrap_or_else(|| {
+      let size = window.size();
+      (size.width as f64, size.height as f64)
+    })
+}
+
+enum WindowMotion {
+  /// Moves from start to end following the progress of the animation
+  Tween { start: FPoint },
+  /// Springs for the x and y coordinates
+  Spring(RefCell<(Spring, Spring)>),
 }
 
 struct WindowAnimationDriver {
   window: Rc<Window>,
-  start: FPoint,
-  end: FPoint,
+  end: Cell<FPoint>,
+  motion: WindowMotion,
 }
 
-impl AnimationDriver for WindowAnimationDriver {
-  fn step(&self, percent: f64) {
+impl WindowAnimationDriver {
+  fn tween(window: Rc<Window>, start: FPoint, end: FPoint) -> WindowAnimationDriver {
+    WindowAnimationDriver {
+      window,
+      end: Cell::new(end),
+      motion: WindowMotion::Tween { start },
+    }
+  }
+
+  fn spring(
+    window: Rc<Window>,
+    start: FPoint,
+    end: FPoint,
+    config: SpringConfig,
+  ) -> WindowAnimationDriver {
+    WindowAnimationDriver {
+      window,
+      end: Cell::new(end),
+      motion: WindowMotion::Spring(RefCell::new((
+        Spring::new(config, start.x, end.x),
+        Spring::new(config, start.y, end.y),
+      ))),
+    }
+  }
+
+  /// Translates the window from its end position to the position of the springs
+  fn apply_springs(&self, springs: &(Spring, Spring)) {
+    let end = self.end.get();
     self.window.set_translate(Displacement {
-      dx: ((self.start.x - self.end.x) * (1.0 - percent)) as i32,
-      dy: ((self.start.y - self.end.y) * (1.0 - percent)) as i32,
+      dx: (springs.0.position - end.x).round() as i32,
+      dy: (springs.1.position - end.y).round() as i32,
     });
   }
+}
+
+impl AnimationDriver for WindowAnimationDriver {
+  fn step(&self, percent: f64) {
+    match self.motion {
+      WindowMotion::Tween { start } => {
+        let end = self.end.get();
+        self.window.set_translate(Displacement {
+          dx: ((start.x - end.x) * (1.0 - percent)) as i32,
+          dy: ((start.y - end.y) * (1.0 - percent)) as i32,
+        });
+      }
+      // Only stepped by progress when animations are disabled
+      WindowMotion::Spring(ref springs) => {
+        if percent >= 1.0 {
+          let mut springs = springs.borrow_mut();
+          springs.0.finish();
+          springs.1.finish();
+          self.apply_springs(&springs);
+        }
+      }
+    }
+  }
+  fn step_physics(&self, dt: Duration) -> bool {
+    match self.motion {
+      WindowMotion::Tween { .. } => true,
+      WindowMotion::Spring(ref springs) => {
+        let mut springs = springs.borrow_mut();
+        springs.0.step(dt);
+        springs.1.step(dt);
+        let at_rest = springs.0.is_at_rest() && springs.1.is_at_rest();
+        if at_rest {
+          springs.0.finish();
+          springs.1.finish();
+        }
+        self.apply_springs(&springs);
+        at_rest
+      }
+    }
+  }
   fn started(&self) {
-    self.window.move_to(self.end.into());
+    self.window.move_to(self.end.get().into());
+    if let WindowMotion::Spring(ref springs) = self.motion {
+      self.apply_springs(&springs.borrow());
+    }
   }
   fn aborted(&self) {
     self.window.set_translate(Displacement::ZERO);
   }
   fn is_conflict(&self, other: &Self) -> AnimationConflict {
     if self.window == other.window {
-      if self.end == other.end {
-        AnimationConflict::Ignore
-      } else {
-        AnimationConflict::Replace
+      match (&self.motion, &other.motion) {
+        (WindowMotion::Spring(_), WindowMotion::Spring(_)) => AnimationConflict::Retarget,
+        _ if self.end == other.end => AnimationConflict::Ignore,
+        _ => AnimationConflict::Replace,
       }
     } else {
       AnimationConflict::NoConflict
     }
   }
+  fn retarget(&self, other: &Self) {
+    let end = other.end.get();
+    self.end.set(end);
+    if let WindowMotion::Spring(ref springs) = self.motion {
+      let mut springs = springs.borrow_mut();
+      springs.0.target = end.x;
+      springs.1.target = end.y;
+      // Keeps the window where it is on screen, while its extents move to the
+      // new target
+      self.window.move_to(end.into());
+      self.apply_springs(&springs);
+    }
+  }
+}
+
+enum SizeMotion {
+  /// Resizes from start to end following the progress of the animation
+  Tween { start: (f64, f64) },
+  /// Springs for the width and height
+  Spring(RefCell<(Spring, Spring)>),
+}
+
+/// Animates the size of a window.
+///
+/// The client is only asked for the end size, as configuring it every frame
+/// would flood it. Until the animation is done and the client has committed
+/// the end size, the window is drawn scaled from whatever size it has
+/// committed to the animated size. The position is not
+/// touched, so this runs alongside position animations.
+struct WindowSizeAnimationDriver {
+  window: Rc<Window>,
+  end: Cell<Size>,
+  /// When the client was asked for the end size
+  requested_at: Cell<Option<Instant>>,
+  /// The size the window is currently drawn at
+  displayed: Cell<(f64, f64)>,
+  motion: SizeMotion,
+}
+
+impl WindowSizeAnimationDriver {
+  fn tween(window: Rc<Window>, start: (f64, f64), end: Size) -> WindowSizeAnimationDriver {
+    WindowSizeAnimationDriver {
+      window,
+      end: Cell::new(end),
+      requested_at: Cell::new(None),
+      displayed: Cell::new(start),
+      motion: SizeMotion::Tween { start },
+    }
+  }
+
+  fn spring(
+    window: Rc<Window>,
+    start: (f64, f64),
+    end: Size,
+    config: SpringConfig,
+  ) -> WindowSizeAnimationDriver {
+    WindowSizeAnimationDriver {
+      window,
+      end: Cell::new(end),
+      requested_at: Cell::new(None),
+      displayed: Cell::new(start),
+      motion: SizeMotion::Spring(RefCell::new((
+        Spring::new(config, start.0, end.width as f64),
+        Spring::new(config, start.1, end.height as f64),
+      ))),
+    }
+  }
+
+  fn request_end_size(&self) {
+    self.window.resize(self.end.get());
+    self.requested_at.set(Some(Instant::now()));
+  }
+
+  /// Scales the window from the size the client has committed to the size
+  fn display_size(&self, width: f64, height: f64) {
+    let (width, height) = (width.max(1.0), height.max(1.0));
+    self.displayed.set((width, height));
+    let committed = self.window.size();
+    self.window.set_render_scale(
+      width / committed.width.max(1) as f64,
+      height / committed.height.max(1) as f64,
+    );
+  }
+}
+
+impl AnimationDriver for WindowSizeAnimationDriver {
+  fn step(&self, percent: f64) {
+    let end = self.end.get();
+    match self.motion {
+      SizeMotion::Tween { start } => {
+        let lerp = |from: f64, to: i32| from + (to as f64 - from) * percent;
+        self.display_size(lerp(start.0, end.width), lerp(start.1, end.height));
+      }
+      // Only stepped by progress when animations are disabled
+      SizeMotion::Spring(ref springs) => {
+        if percent >= 1.0 {
+          let mut springs = springs.borrow_mut();
+          springs.0.finish();
+          springs.1.finish();
+          self.display_size(springs.0.position, springs.1.position);
+        }
+      }
+    }
+  }
+  fn step_physics(&self, dt: Duration) -> bool {
+    match self.motion {
+      SizeMotion::Tween { .. } => true,
+      SizeMotion::Spring(ref springs) => {
+        let mut springs = springs.borrow_mut();
+        springs.0.step(dt);
+        springs.1.step(dt);
+        let at_rest = springs.0.is_at_rest() && springs.1.is_at_rest();
+        if at_rest {
+          springs.0.finish();
+          springs.1.finish();
+        }
+        self.display_size(springs.0.position, springs.1.position);
+        at_rest
+      }
+    }
+  }
+  fn is_settled(&self) -> bool {
+    // Clients may not be able to take the exact size, e.g. due to a minimum
+    // size, so they get a while before the window is drawn as committed
+    self.window.size() == self.end.get()
+      || self.requested_at.get().map_or(true, |requested_at| {
+        requested_at.elapsed() > RESIZE_COMMIT_TIMEOUT
+      })
+  }
+  fn started(&self) {
+    self.request_end_size();
+    let (width, height) = self.displayed.get();
+    self.display_size(width, height);
+  }
+  fn aborted(&self) {
+    self.window.set_render_scale(1.0, 1.0);
+  }
+  fn completed(&self) {
+    self.window.set_render_scale(1.0, 1.0);
+  }
+  fn is_conflict(&self, other: &Self) -> AnimationConflict {
+    if self.window == other.window {
+      match (&self.motion, &other.motion) {
+        (SizeMotion::Spring(_), SizeMotion::Spring(_)) => AnimationConflict::Retarget,
+        _ if self.end == other.end => AnimationConflict::Ignore,
+        _ => AnimationConflict::Replace,
+      }
+    } else {
+      AnimationConflict::NoConflict
+    }
+  }
+  fn retarget(&self, other: &Self) {
+    let end = other.end.get();
+    self.end.set(end);
+    self.request_end_size();
+    if let SizeMotion::Spring(ref springs) = self.motion {
+      let mut springs = springs.borrow_mut();
+      springs.0.target = end.width as f64;
+      springs.1.target = end.height as f64;
+    }
+  }
 }
@@ -0,0 +1,131 @@
+use std::rc::Rc;
+use wlral::{output::Output, window::Window};
+
+/// An ongoing walk through windows in most recently used order.
+///
+/// The order is captured when the cycle starts, so that previewing windows
+/// does not change it.
+#[derive(Debug)]
+pub(crate) struct WindowCycle<W = Rc<Window>> {
+  windows: Vec<W>,
+  index: usize,
+  /// The output that was active when the cycle started, where the workspaces
+  /// of previewed windows are shown
+  output: Option<Rc<Output>>,
+}
+
+impl<W: Clone + PartialEq> WindowCycle<W> {
+  pub(crate) fn new(windows: Vec<W>, output: Option<Rc<Output>>) -> Option<WindowCycle<W>> {
+    if windows.is_empty() {
+      None
+    } else {
+      Some(WindowCycle {
+        windows,
+        index: 0,
+        output,
+      })
+    }
+  }
+
+  pub(crate) fn output(&self) -> Option<Rc<Output>> {
+    self.output.clone()
+  }
+
+  pub(crate) fn current(&self) -> W {
+    self.windows[self.index].clone()
+  }
+
+  /// Steps forwards or backwards, wrapping around at the ends
+  pub(crate) fn step(&mut self, forward: bool) {
+    let len = self.windows.len();
+    self.index = if forward {
+      (self.index + 1) % len
+    } else {
+      (self.index + len - 1) % len
+    };
+  }
+
+  /// Removes a window, returning false if it was the current window
+  pub(crate) fn remove(&mut self, window: &W) -> bool {
+    match self.windows.iter().position(|w| w == window) {
+      Some(index) if index == self.index => false,
+      Some(index) => {
+        self.windows.remove(index);
+        if index < self.index {
+          self.index -= 1;
+        }
+        true
+      }
+      None => true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::entities::MruList;
+
+  /// Cycles through the windows of the MRU list like CycleWindows does,
+  /// returning the previewed windows and promoting the last one
+  fn cycle(mru: &mut MruList<u32>, steps: &[bool]) -> Vec<u32> {
+    let mut cycle = WindowCycle::new(mru.iter().cloned().collect(), None).unwrap();
+    let previewed = steps
+      .iter()
+      .map(|forward| {
+        cycle.step(*forward);
+        cycle.current()
+      })
+      .collect();
+    mru.promote(&cycle.current());
+    previewed
+  }
+
+  fn mru_list(windows: &[u32]) -> MruList<u32> {
+    let mut mru = MruList::new();
+    for window in windows {
+      mru.push(*window);
+    }
+    mru
+  }
+
+  #[test]
+  fn starts_with_the_previously_used_window() {
+    let mut mru = mru_list(&[1, 2, 3]);
+    assert_eq!(cycle(&mut mru, &[true]), vec![2]);
+    assert_eq!(mru.iter().cloned().collect::<Vec<_>>(), vec![2, 3, 1]);
+  }
+
+  #[test]
+  fn cycling_once_switches_between_the_last_two_windows() {
+    let mut mru = mru_list(&[1, 2, 3]);
+    assert_eq!(cycle(&mut mru, &[true]), vec![2]);
+    assert_eq!(cycle(&mut mru, &[true]), vec![3]);
+    assert_eq!(cycle(&mut mru, &[true]), vec![2]);
+  }
+
+  #[test]
+  fn wraps_around_in_both_directions() {
+    let mut mru = mru_list(&[1, 2, 3]);
+    assert_eq!(cycle(&mut mru, &[true, true, true]), vec![2, 1, 3]);
+    let mut mru = mru_list(&[1, 2, 3]);
+    assert_eq!(cycle(&mut mru, &[false, false]), vec![1, 2]);
+  }
+
+  #[test]
+  fn removing_windows_keeps_the_current_window() {
+    let mut cycle = WindowCycle::new(vec![3, 2, 1], None).unwrap();
+    cycle.step(true);
+    assert!(cycle.remove(&3));
+    assert_eq!(cycle.current(), 2);
+    assert!(cycle.remove(&4));
+    assert!(!cycle.remove(&2));
+    cycle.step(true);
+    assert_eq!(cycle.current(), 1);
+  }
+
+  #[test]
+  fn no_windows_no_cycle() {
+    assert!(WindowCycle::<u32>::new(vec![], None).is_none());
+  }
+}
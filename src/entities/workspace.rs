@@ -1,41 +1,62 @@
-use super::MruList;
+use super::{Column, MruList};
 use crate::actions::Direction;
 use log::trace;
 use std::{
   cell::{Ref, RefCell},
+  cmp,
   rc::Rc,
 };
-use wlral::{geometry::Point, window::Window};
+use wlral::{
+  geometry::{Point, Rectangle},
+  window::Window,
+};
 
 pub(crate) enum WorkspacePosition {
   ActiveWindow,
   Start,
   End,
   Coordinate(Point),
+  /// Index of the column to insert the window at
+  Column(usize),
 }
 
 #[derive(Debug)]
 pub(crate) struct Workspace {
+  index: usize,
   scroll_left: RefCell<i32>,
-  windows: RefCell<Vec<Rc<Window>>>,
+  /// Vertical offset of all windows while sliding in or out of an output
+  slide_offset: RefCell<i32>,
+  columns: RefCell<Vec<Column>>,
+  floating_windows: RefCell<Vec<Rc<Window>>>,
+  /// The fullscreen window and its extents from before it went fullscreen
+  fullscreen_window: RefCell<Option<(Rc<Window>, Rectangle)>>,
   mru_windows: RefCell<MruList<Rc<Window>>>,
 }
 
 impl PartialEq<Workspace> for Workspace {
   fn eq(&self, other: &Workspace) -> bool {
-    RefCell::as_ptr(&self.windows) == RefCell::as_ptr(&other.windows)
+    RefCell::as_ptr(&self.columns) == RefCell::as_ptr(&other.columns)
   }
 }
 
 impl Workspace {
-  pub(crate) fn new() -> Workspace {
+  pub(crate) fn new(index: usize) -> Workspace {
     Workspace {
+      index,
       scroll_left: RefCell::new(0),
-      windows: RefCell::new(vec![]),
+      slide_offset: RefCell::new(0),
+      columns: RefCell::new(vec![]),
+      floating_windows: RefCell::new(vec![]),
+      fullscreen_window: RefCell::new(None),
       mru_windows: RefCell::new(MruList::new()),
     }
   }
 
+  /// Stable, 1 based, index of the workspace
+  pub(crate) fn index(&self) -> usize {
+    self.index
+  }
+
   pub(crate) fn scroll_left(&self) -> i32 {
     *self.scroll_left.borrow()
   }
@@ -43,23 +64,99 @@ impl Workspace {
     *self.scroll_left.borrow_mut() = scroll_left;
   }
 
+  /// How far the windows are translated while the workspace slides in or
+  /// out, 0 when it is not sliding
+  pub(crate) fn slide_offset(&self) -> i32 {
+    *self.slide_offset.borrow()
+  }
+  pub(crate) fn set_slide_offset(&self, slide_offset: i32) {
+    *self.slide_offset.borrow_mut() = slide_offset;
+  }
+
   pub(crate) fn has_window(&self, window: &Window) -> bool {
-    self.windows.borrow().iter().any(|w| w.as_ref() == window)
+    self.columns.borrow().iter().any(|c| c.contains(window)) || self.is_floating(window)
+  }
+  pub(crate) fn is_floating(&self, window: &Window) -> bool {
+    self
+      .floating_windows
+      .borrow()
+      .iter()
+      .any(|w| w.as_ref() == window)
   }
 
-  pub(crate) fn windows(&self) -> Ref<Vec<Rc<Window>>> {
-    self.windows.borrow()
+  pub(crate) fn columns(&self) -> Ref<Vec<Column>> {
+    self.columns.borrow()
+  }
+  /// The tiled windows, column by column from top to bottom
+  pub(crate) fn windows(&self) -> Vec<Rc<Window>> {
+    self
+      .columns()
+      .iter()
+      .flat_map(|column| column.windows().iter().cloned())
+      .collect()
+  }
+  pub(crate) fn floating_windows(&self) -> Ref<Vec<Rc<Window>>> {
+    self.floating_windows.borrow()
+  }
+  /// The tiled windows followed by the floating windows
+  pub(crate) fn all_windows(&self) -> Vec<Rc<Window>> {
+    let mut windows = self.windows();
+    windows.extend(self.floating_windows().iter().cloned());
+    windows
+  }
+  /// Hides or shows all windows, as the workspace leaves or gets an output
+  pub(crate) fn set_windows_hidden(&self, hidden: bool) {
+    for window in self.all_windows() {
+      window.set_hidden(hidden);
+    }
   }
   pub(crate) fn mru_windows(&self) -> Ref<MruList<Rc<Window>>> {
     self.mru_windows.borrow()
   }
-  fn index_of_window(&self, window: &Rc<Window>) -> usize {
+  pub(crate) fn fullscreen_window(&self) -> Option<Rc<Window>> {
     self
-      .windows()
+      .fullscreen_window
+      .borrow()
+      .as_ref()
+      .map(|(window, _)| window.clone())
+  }
+  pub(crate) fn set_fullscreen_window(&self, window: Rc<Window>) {
+    let extents = window.extents();
+    *self.fullscreen_window.borrow_mut() = Some((window, extents));
+  }
+  /// Clears the fullscreen window, returning it with its extents from before
+  /// it went fullscreen
+  pub(crate) fn take_fullscreen_window(&self) -> Option<(Rc<Window>, Rectangle)> {
+    self.fullscreen_window.borrow_mut().take()
+  }
+  /// The most recently used window that is not floating
+  pub(crate) fn active_tiled_window(&self) -> Option<Rc<Window>> {
+    self
+      .mru_windows()
+      .iter()
+      .find(|w| !self.is_floating(w))
+      .cloned()
+  }
+  /// The most recently used window in the column
+  pub(crate) fn active_window_in_column(&self, column: &Column) -> Option<Rc<Window>> {
+    self
+      .mru_windows()
+      .iter()
+      .find(|w| column.contains(w))
+      .cloned()
+  }
+
+  /// Returns the column index and the index in that column of the window
+  fn position_of_window(&self, window: &Window) -> (usize, usize) {
+    self
+      .columns()
       .iter()
       .enumerate()
-      .find(|(_, w)| *w == window)
-      .map(|(index, _)| index)
+      .find_map(|(column_index, column)| {
+        column
+          .index_of_window(window)
+          .map(|row_index| (column_index, row_index))
+      })
       .expect("window not found in workspace")
   }
   pub(crate) fn window_by_direction(
@@ -67,56 +164,166 @@ impl Workspace {
     from: &Rc<Window>,
     direction: Direction,
   ) -> Option<Rc<Window>> {
-    let from_index = self.index_of_window(from);
+    if self.is_floating(from) {
+      // Leave the floating layer for the strip
+      return self.active_tiled_window();
+    }
+    let (column_index, row_index) = self.position_of_window(from);
     trace!(
-      "window_by_direction from: \"{:?}\", direction: {:?}, index: {}",
+      "window_by_direction from: \"{:?}\", direction: {:?}, column: {}, row: {}",
       from.title(),
       direction,
-      from_index
+      column_index,
+      row_index
     );
 
-    let index = match direction {
-      Direction::Left => {
-        if from_index == 0 {
-          return None;
-        }
-        from_index - 1
-      }
-      Direction::Right => from_index + 1,
-    };
+    let columns = self.columns();
+    match direction {
+      Direction::Left | Direction::Right => {
+        let index = match direction {
+          Direction::Left => {
+            if column_index == 0 {
+              return None;
+            }
+            column_index - 1
+          }
+          _ => column_index + 1,
+        };
 
-    trace!(
-      "window_by_direction index: {}, num windows: {}",
-      index,
-      self.windows().len(),
-    );
+        trace!(
+          "window_by_direction column: {}, num columns: {}",
+          index,
+          columns.len(),
+        );
+
+        columns
+          .get(index)
+          .and_then(|column| self.active_window_in_column(column))
+      }
+      Direction::Up | Direction::Down => {
+        let column = &columns[column_index];
+        let index = match direction {
+          Direction::Up => {
+            if row_index == 0 {
+              return None;
+            }
+            row_index - 1
+          }
+          _ => row_index + 1,
+        };
 
-    self.windows().get(index).cloned()
+        column.windows().get(index).cloned()
+      }
+    }
   }
+  /// Moves the column of the window left or right, or the window up or down
+  /// in its column.
   pub(crate) fn move_window(&self, window: &Rc<Window>, direction: Direction) -> Result<(), ()> {
-    let from_index = self.index_of_window(window);
+    if self.is_floating(window) {
+      return Err(());
+    }
+    let (column_index, row_index) = self.position_of_window(window);
+    let mut columns = self.columns.borrow_mut();
 
-    let to_index = match direction {
-      Direction::Left => {
-        if from_index == 0 {
+    match direction {
+      Direction::Left | Direction::Right => {
+        let to_index = match direction {
+          Direction::Left => {
+            if column_index == 0 {
+              return Err(());
+            }
+            column_index - 1
+          }
+          _ => column_index + 1,
+        };
+
+        if to_index == columns.len() {
           return Err(());
         }
-        from_index - 1
+
+        columns.swap(column_index, to_index);
+        trace!(
+          "Moved window \"{:?}\" {:?} to column {}",
+          window.title(),
+          direction,
+          to_index
+        );
       }
-      Direction::Right => from_index + 1,
-    };
+      Direction::Up | Direction::Down => {
+        let column = &mut columns[column_index];
+        let to_index = match direction {
+          Direction::Up => {
+            if row_index == 0 {
+              return Err(());
+            }
+            row_index - 1
+          }
+          _ => row_index + 1,
+        };
 
-    if to_index == self.windows().len() {
+        if to_index == column.len() {
+          return Err(());
+        }
+
+        column.swap(row_index, to_index);
+        trace!(
+          "Moved window \"{:?}\" {:?} to row {}",
+          window.title(),
+          direction,
+          to_index
+        );
+      }
+    }
+    Ok(())
+  }
+  /// Moves the closest window of the neighbouring column, in the specified
+  /// direction, to the bottom of the column of the window.
+  pub(crate) fn pull_window(&self, window: &Rc<Window>, direction: Direction) -> Result<(), ()> {
+    if self.is_floating(window) {
       return Err(());
     }
+    let (column_index, _) = self.position_of_window(window);
+    let mut columns = self.columns.borrow_mut();
+
+    let from_index = match direction {
+      Direction::Left if column_index > 0 => column_index - 1,
+      Direction::Right if column_index + 1 < columns.len() => column_index + 1,
+      _ => return Err(()),
+    };
+    let pulled_window = match direction {
+      Direction::Left => columns[from_index].windows().last().cloned(),
+      _ => columns[from_index].windows().first().cloned(),
+    }
+    .expect("Columns should never be empty");
 
-    self.windows.borrow_mut().swap(from_index, to_index);
     trace!(
-      "Moved window \"{:?}\" {:?} to index {}",
-      window.title(),
-      direction,
-      to_index
+      "Pulling window \"{:?}\" into the column of \"{:?}\"",
+      pulled_window.title(),
+      window.title()
     );
+    columns[from_index].remove(&pulled_window);
+    columns[column_index].push(pulled_window);
+    if columns[from_index].is_empty() {
+      columns.remove(from_index);
+    }
+    Ok(())
+  }
+  /// Moves the window out of its column, into a new column to the right.
+  pub(crate) fn expel_window(&self, window: &Rc<Window>) -> Result<(), ()> {
+    if self.is_floating(window) {
+      return Err(());
+    }
+    let (column_index, _) = self.position_of_window(window);
+    let mut columns = self.columns.borrow_mut();
+    if columns[column_index].len() == 1 {
+      return Err(());
+    }
+
+    trace!("Expelling window \"{:?}\" from its column", window.title());
+    let window = columns[column_index]
+      .remove(window)
+      .expect("window not found in column");
+    columns.insert(column_index + 1, Column::new(window));
     Ok(())
   }
 
@@ -127,36 +334,24 @@ impl Workspace {
     assert!(window.can_receive_focus());
     let index = match position {
       WorkspacePosition::ActiveWindow => {
-        let active_window = self.mru_windows().top().cloned();
+        let active_window = self.active_tiled_window();
         if let Some(active_window) = active_window {
-          let index = self
-            .windows
-            .borrow()
-            .iter()
-            .enumerate()
-            .find_map(|(index, window)| {
-              if *window == active_window {
-                Some(index)
-              } else {
-                None
-              }
-            })
-            .expect("Window should be in both mru_windows and windows");
-
-          index + 1
+          let (column_index, _) = self.position_of_window(&active_window);
+          column_index + 1
         } else {
           0
         }
       }
       WorkspacePosition::Start => 0,
-      WorkspacePosition::End => self.windows.borrow().len(),
+      WorkspacePosition::End => self.columns().len(),
+      WorkspacePosition::Column(index) => cmp::min(index, self.columns().len()),
       WorkspacePosition::Coordinate(point) => {
         self
-          .windows()
+          .columns()
           .iter()
           .enumerate()
-          .fold(0, |last_index, (current_index, window)| {
-            let extents = window.extents();
+          .fold(0, |last_index, (current_index, column)| {
+            let extents = column.windows()[0].extents();
             if point.x < extents.left() {
               last_index
             } else if point.x < extents.center_x() {
@@ -169,16 +364,61 @@ impl Workspace {
     };
 
     trace!(
-      "Adding window \"{:?}\" to workspace at index {}",
+      "Adding window \"{:?}\" to workspace at column {}",
       window.title(),
       index
     );
-    self.windows.borrow_mut().insert(index, window.clone());
+    self
+      .columns
+      .borrow_mut()
+      .insert(index, Column::new(window.clone()));
+    self.mru_windows.borrow_mut().push(window);
+  }
+  /// Adds the window to the column of another window, at the specified row.
+  pub(crate) fn add_window_to_column(
+    &self,
+    window: Rc<Window>,
+    column_window: &Rc<Window>,
+    row: usize,
+  ) {
+    assert!(window.can_receive_focus());
+    let (column_index, _) = self.position_of_window(column_window);
+    trace!(
+      "Adding window \"{:?}\" to workspace at column {}, row {}",
+      window.title(),
+      column_index,
+      row
+    );
+    {
+      let mut columns = self.columns.borrow_mut();
+      let column = &mut columns[column_index];
+      let row = cmp::min(row, column.len());
+      column.insert(row, window.clone());
+    }
+    self.mru_windows.borrow_mut().push(window);
+  }
+  pub(crate) fn add_floating_window(&self, window: Rc<Window>) {
+    assert!(window.can_receive_focus());
+    trace!(
+      "Adding floating window \"{:?}\" to workspace",
+      window.title()
+    );
+    self.floating_windows.borrow_mut().push(window.clone());
     self.mru_windows.borrow_mut().push(window);
   }
   pub(crate) fn remove_window(&self, window: &Rc<Window>) {
     trace!("Removing window \"{:?}\" from workspace", window.title());
-    self.windows.borrow_mut().retain(|w| w != window);
+    {
+      let mut columns = self.columns.borrow_mut();
+      for column in columns.iter_mut() {
+        column.remove(window);
+      }
+      columns.retain(|column| !column.is_empty());
+    }
+    self.floating_windows.borrow_mut().retain(|w| w != window);
+    if self.fullscreen_window().as_ref() == Some(window) {
+      self.take_fullscreen_window();
+    }
     self.mru_windows.borrow_mut().remove(window);
   }
 }
@@ -0,0 +1,112 @@
+use super::{window::WindowAnimations, Workspace};
+use crate::animation::{
+  Animation, AnimationConfig, AnimationConflict, AnimationDriver, AnimationManager, Easing,
+};
+use std::{rc::Rc, time::Duration};
+use wlral::geometry::Displacement;
+
+const DEFAULT_WORKSPACE_SLIDE_DURATION_MS: u64 = 250;
+
+pub(crate) trait WorkspaceAnimations {
+  /// Slides the to workspace in from distance pixels below, or above if
+  /// negative, while the from workspace slides out the opposite way.
+  ///
+  /// The windows are translated while they slide, so that they keep their
+  /// positions on the output. Once the slide is done the windows of the from
+  /// workspace are hidden.
+  fn slide_workspaces(
+    &self,
+    from: Option<Rc<Workspace>>,
+    to: Rc<Workspace>,
+    distance: i32,
+    config: &AnimationConfig,
+  );
+}
+
+impl WorkspaceAnimations for AnimationManager {
+  fn slide_workspaces(
+    &self,
+    from: Option<Rc<Workspace>>,
+    to: Rc<Workspace>,
+    distance: i32,
+    config: &AnimationConfig,
+  ) {
+    // Running moves would fight over the translation of the windows
+    let windows = from
+      .iter()
+      .chain(Some(&to))
+      .flat_map(|workspace| workspace.all_windows());
+    for window in windows {
+      self.stop_window_position(&window);
+    }
+    self.start(
+      Animation::immediate(
+        Duration::from_millis(
+          config
+            .duration
+            .unwrap_or(DEFAULT_WORKSPACE_SLIDE_DURATION_MS),
+        ),
+        WorkspaceSlideDriver {
+          from,
+          to: to.clone(),
+          distance,
+        },
+      )
+      .with_easing(config.easing.unwrap_or(Easing::EaseOutCubic)),
+    );
+    // After starting, so that a conflicting slide has been finished first
+    set_slide_offset(&to, distance);
+    to.set_windows_hidden(false);
+  }
+}
+
+struct WorkspaceSlideDriver {
+  from: Option<Rc<Workspace>>,
+  to: Rc<Workspace>,
+  distance: i32,
+}
+
+/// Translates all windows of the workspace by its slide offset
+fn set_slide_offset(workspace: &Workspace, offset: i32) {
+  workspace.set_slide_offset(offset);
+  for window in workspace.all_windows() {
+    window.set_translate(Displacement { dx: 0, dy: offset });
+  }
+}
+
+impl AnimationDriver for WorkspaceSlideDriver {
+  fn step(&self, percent: f64) {
+    let distance = self.distance as f64;
+    set_slide_offset(&self.to, (distance * (1.0 - percent)).round() as i32);
+    if let Some(ref from) = self.from {
+      set_slide_offset(from, (-distance * percent).round() as i32);
+    }
+  }
+  fn completed(&self) {
+    set_slide_offset(&self.to, 0);
+    if let Some(ref from) = self.from {
+      from.set_windows_hidden(true);
+      set_slide_offset(from, 0);
+    }
+  }
+  fn aborted(&self) {
+    self.step(1.0);
+    self.completed();
+  }
+  fn is_conflict(&self, other: &Self) -> AnimationConflict {
+    let workspaces = |driver: &WorkspaceSlideDriver| {
+      let mut workspaces = vec![driver.to.clone()];
+      workspaces.extend(driver.from.clone());
+      workspaces
+    };
+    let other_workspaces = workspaces(other);
+    if workspaces(self)
+      .iter()
+      .any(|workspace| other_workspaces.contains(workspace))
+    {
+      AnimationConflict::Replace
+    } else {
+      AnimationConflict::NoConflict
+    }
+  }
+}
@@ -0,0 +1,63 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use wlral::geometry::{Point, Rectangle, Size};
+
+/// Empty space around tiled windows, in pixels
+#[derive(Default, Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct GapsConfig {
+  /// Space between columns, and between windows in a column
+  pub(crate) inner: i32,
+  /// Space between the windows and the edges of the output
+  pub(crate) outer: i32,
+  /// Space reserved at the top of the output, in addition to outer
+  pub(crate) top: i32,
+  /// Space reserved at the bottom of the output, in addition to outer
+  pub(crate) bottom: i32,
+}
+
+impl GapsConfig {
+  pub(crate) fn validate(config: &Config) -> Result<(), Box<dyn Error>> {
+    let gaps = &config.gaps;
+    for (name, value) in [
+      ("inner", gaps.inner),
+      ("outer", gaps.outer),
+      ("top", gaps.top),
+      ("bottom", gaps.bottom),
+    ]
+    .iter()
+    {
+      if *value < 0 {
+        return Err(format!("gaps.{}: must not be negative", name).into());
+      }
+    }
+
+    Ok(())
+  }
+
+  /// The part of the usable area of an output that tiled windows are placed in
+  pub(crate) fn layout_area(&self, usable_area: Rectangle) -> Rectangle {
+    let top = self.outer + self.top;
+    let bottom = self.outer + self.bottom;
+    Rectangle {
+      top_left: Point {
+        x: usable_area.left() + self.outer,
+        y: usable_area.top() + top,
+      },
+      size: Size {
+        width: (usable_area.width() - 2 * self.outer).max(0),
+        height: (usable_area.height() - top - bottom).max(0),
+      },
+    }
+  }
+
+  /// Width of a column taking the fraction of the layout area, such that
+  /// columns with fractions summing to 1 fill the area including their gaps.
+  ///
+  /// Small fractions with large gaps still give a column at least a pixel wide.
+  pub(crate) fn column_width(&self, layout_area: &Rectangle, fraction: f32) -> i32 {
+    let width = ((layout_area.width() + self.inner) as f32 * fraction).round() as i32;
+    (width - self.inner).max(1)
+  }
+}
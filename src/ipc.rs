@@ -0,0 +1,357 @@
+pub(crate) mod protocol;
+
+use crate::{
+  actions::finish_cycling_windows, entities::Workspace, shortcuts::ActionShortcut,
+  window_manager::CascadeWindowManager,
+};
+use log::{debug, error, trace, warn};
+use protocol::*;
+use serde::Serialize;
+use std::{
+  cell::RefCell,
+  env,
+  error::Error,
+  fs,
+  io::{self, BufRead, BufReader, Read, Write},
+  os::unix::{
+    fs::PermissionsExt,
+    io::{AsRawFd, RawFd},
+    net::{UnixListener, UnixStream},
+  },
+  path::PathBuf,
+  process,
+  rc::Rc,
+  sync::mpsc::{self, Receiver, Sender},
+  thread,
+};
+use wlral::{output::Output, window::Window};
+
+type PendingRequest = (IpcRequest, Sender<IpcResponse>);
+
+/// Listens for clients on a unix socket.
+///
+/// Clients are served on their own threads, which pass requests on to the
+/// compositor thread and then write to the wake socket. The compositor
+/// watches [`IpcServer::wake_fd`] in its event loop and answers the requests
+/// by calling [`IpcServer::dispatch`].
+pub(crate) struct IpcServer {
+  socket_path: PathBuf,
+  requests: Receiver<PendingRequest>,
+  wake: UnixStream,
+  new_subscribers: Receiver<Sender<IpcEvent>>,
+  subscribers: RefCell<Vec<Sender<IpcEvent>>>,
+}
+
+impl IpcServer {
+  pub(crate) fn init() -> Result<IpcServer, Box<dyn Error>> {
+    // Any client that can connect may run actions, so the socket is only
+    // created in the private runtime directory
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|_| "XDG_RUNTIME_DIR is not set")?;
+    let socket_path = PathBuf::from(runtime_dir).join(format!("cascade.{}.sock", process::id()));
+    if socket_path.exists() {
+      fs::remove_file(&socket_path)?;
+    }
+    let listener = UnixListener::bind(&socket_path)?;
+    fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o600))?;
+    // Exported so that clients started by Cascade can find the socket
+    env::set_var(SOCKET_ENV, &socket_path);
+    debug!("Listening for IPC clients on {:?}", socket_path);
+
+    let (wake, waker) = UnixStream::pair()?;
+    wake.set_nonblocking(true)?;
+
+    let (sender, requests) = mpsc::channel();
+    let (subscribe_sender, new_subscribers) = mpsc::channel();
+    thread::spawn(move || {
+      for stream in listener.incoming() {
+        match stream.and_then(|stream| Ok((stream, waker.try_clone()?))) {
+          Ok((stream, waker)) => {
+            let sender = sender.clone();
+            let subscribe_sender = subscribe_sender.clone();
+            thread::spawn(move || handle_client(stream, sender, waker, subscribe_sender));
+          }
+          Err(error) => {
+            warn!("Failed to accept IPC client: {}", error);
+          }
+        }
+      }
+    });
+
+    Ok(IpcServer {
+      socket_path,
+      requests,
+      wake,
+      new_subscribers,
+      subscribers: RefCell::new(vec![]),
+    })
+  }
+
+  /// Becomes readable when there are requests waiting to be dispatched
+  pub(crate) fn wake_fd(&self) -> RawFd {
+    self.wake.as_raw_fd()
+  }
+
+  pub(crate) fn dispatch(&self, handler: impl Fn(IpcRequest) -> IpcResponse) {
+    let mut buffer = [0; 64];
+    while let Ok(read) = (&self.wake).read(&mut buffer) {
+      if read == 0 {
+        break;
+      }
+    }
+    while let Ok((request, response_sender)) = self.requests.try_recv() {
+      debug!("Handling IPC request {:?}", request);
+      // The client may have disconnected while waiting, which is fine
+      let _ = response_sender.send(handler(request));
+    }
+  }
+
+  pub(crate) fn has_subscribers(&self) -> bool {
+    self.accept_subscribers();
+    !self.subscribers.borrow().is_empty()
+  }
+
+  pub(crate) fn broadcast(&self, event: IpcEvent) {
+    self.accept_subscribers();
+    trace!("Broadcasting IPC event {:?}", event);
+    self
+      .subscribers
+      .borrow_mut()
+      .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+  }
+
+  fn accept_subscribers(&self) {
+    let mut subscribers = self.subscribers.borrow_mut();
+    while let Ok(subscriber) = self.new_subscribers.try_recv() {
+      subscribers.push(subscriber);
+    }
+  }
+}
+
+impl Drop for IpcServer {
+  fn drop(&mut self) {
+    if let Err(error) = fs::remove_file(&self.socket_path) {
+      warn!(
+        "Could not remove IPC socket {:?}: {}",
+        self.socket_path, error
+      );
+    }
+  }
+}
+
+fn handle_client(
+  stream: UnixStream,
+  requests: Sender<PendingRequest>,
+  mut waker: UnixStream,
+  subscribe: Sender<Sender<IpcEvent>>,
+) {
+  let reader = match stream.try_clone() {
+    Ok(stream) => BufReader::new(stream),
+    Err(error) => {
+      error!("Failed to read from IPC client: {}", error);
+      return;
+    }
+  };
+  let mut writer = stream;
+
+  for line in reader.lines() {
+    let line = match line {
+      Ok(line) => line,
+      Err(error) => {
+        debug!("IPC client disconnected: {}", error);
+        return;
+      }
+    };
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    let response = match serde_json::from_str::<IpcRequest>(&line) {
+      Ok(IpcRequest::Subscribe) => {
+        let (event_sender, events) = mpsc::channel();
+        if subscribe.send(event_sender).is_err() {
+          return;
+        }
+        if write_message(&mut writer, &IpcResponse::Success).is_err() {
+          return;
+        }
+        for event in events {
+          if let Err(error) = write_message(&mut writer, &event) {
+            debug!("IPC subscriber disconnected: {}", error);
+            return;
+          }
+        }
+        return;
+      }
+      Ok(request) => {
+        let (response_sender, response) = mpsc::channel();
+        if requests.send((request, response_sender)).is_err() {
+          return;
+        }
+        if let Err(error) = waker.write_all(&[0]) {
+          error!(
+            "Failed to wake the compositor for an IPC request: {}",
+            error
+          );
+          return;
+        }
+        match response.recv() {
+          Ok(response) => response,
+          Err(_) => return,
+        }
+      }
+      Err(error) => IpcResponse::Error(format!("Invalid request: {}", error)),
+    };
+
+    if let Err(error) = write_message(&mut writer, &response) {
+      debug!("IPC client disconnected: {}", error);
+      return;
+    }
+  }
+}
+
+fn write_message(writer: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+  serde_json::to_writer(&mut *writer, message)?;
+  writer.write_all(b"\n")
+}
+
+pub(crate) fn handle_request(wm: &CascadeWindowManager, request: IpcRequest) -> IpcResponse {
+  match request {
+    IpcRequest::Action(action) => {
+      action.triggered(wm);
+      // Unless a cycle was started with modifiers held on the keyboard, there
+      // is no release to end it
+      if let ActionShortcut::CycleWindows { .. } = action {
+        if !wm.keyboard.modifiers_held() {
+          finish_cycling_windows(wm);
+        }
+      }
+      IpcResponse::Success
+    }
+    IpcRequest::GetOutputs => IpcResponse::Outputs(
+      wm.output_manager
+        .outputs()
+        .iter()
+        .map(|output| output_info(wm, output))
+        .collect(),
+    ),
+    IpcRequest::GetWorkspaces => IpcResponse::Workspaces(
+      wm.mru_workspaces()
+        .iter()
+        .map(|workspace| workspace_info(wm, workspace))
+        .collect(),
+    ),
+    IpcRequest::GetWindows => {
+      let mut windows = wm
+        .mru_workspaces()
+        .iter()
+        .flat_map(|workspace| {
+          let mut windows = workspace.windows().clone();
+          windows.extend(workspace.floating_windows().iter().cloned());
+          windows
+        })
+        .collect::<Vec<_>>();
+      // Hidden scratchpad windows are not in any workspace
+      windows.extend(
+        wm.scratchpad
+          .windows()
+          .iter()
+          .filter(|w| wm.workspace_by_window(&w.window).is_none())
+          .map(|w| w.window.clone()),
+      );
+      IpcResponse::Windows(
+        windows
+          .iter()
+          .map(|window| window_info(wm, window))
+          .collect(),
+      )
+    }
+    IpcRequest::GetFocusedWindow => IpcResponse::FocusedWindow(
+      wm.window_manager
+        .focused_window()
+        .map(|window| window_info(wm, &window)),
+    ),
+    IpcRequest::GetKeyboardLayout => {
+      IpcResponse::KeyboardLayout(wm.keyboard_devices.active_layout(wm))
+    }
+    IpcRequest::GetKeyboardMode => IpcResponse::KeyboardMode(wm.keyboard.mode()),
+    IpcRequest::Subscribe => {
+      // Subscriptions are set up by the client thread
+      IpcResponse::Error("Subscribe can not be dispatched".to_string())
+    }
+  }
+}
+
+pub(crate) fn window_id(window: &Rc<Window>) -> usize {
+  Rc::as_ptr(window) as usize
+}
+
+pub(crate) fn workspace_id(workspace: &Rc<Workspace>) -> usize {
+  Rc::as_ptr(workspace) as usize
+}
+
+pub(crate) fn output_info(wm: &CascadeWindowManager, output: &Rc<Output>) -> OutputInfo {
+  let extents = output.extents();
+  let workspace = wm.output_workspaces.borrow().get(output).cloned();
+  let active_output = wm
+    .mru_workspaces()
+    .top()
+    .and_then(|workspace| wm.output_by_workspace(workspace));
+  OutputInfo {
+    name: output.name().to_string(),
+    x: extents.left(),
+    y: extents.top(),
+    width: extents.width(),
+    height: extents.height(),
+    workspace: workspace.as_ref().map(workspace_id),
+    focused: active_output.as_ref() == Some(output),
+  }
+}
+
+pub(crate) fn workspace_info(
+  wm: &CascadeWindowManager,
+  workspace: &Rc<Workspace>,
+) -> WorkspaceInfo {
+  WorkspaceInfo {
+    id: workspace_id(workspace),
+    index: workspace.index(),
+    name: wm
+      .config()
+      .workspace_name(workspace.index())
+      .map(str::to_string),
+    output: wm
+      .output_by_workspace(workspace)
+      .map(|output| output.name().to_string()),
+    focused: wm.mru_workspaces().top() == Some(workspace),
+    scroll_left: workspace.scroll_left(),
+    windows: workspace.windows().iter().map(window_id).collect(),
+    columns: workspace
+      .columns()
+      .iter()
+      .map(|column| column.windows().iter().map(window_id).collect())
+      .collect(),
+    floating_windows: workspace.floating_windows().iter().map(window_id).collect(),
+  }
+}
+
+pub(crate) fn window_info(wm: &CascadeWindowManager, window: &Rc<Window>) -> WindowInfo {
+  let extents = window.extents();
+  let workspace = wm.workspace_by_window(window);
+  WindowInfo {
+    id: window_id(window),
+    title: window.title(),
+    app_id: window.app_id(),
+    workspace: workspace.as_ref().map(workspace_id),
+    x: extents.left(),
+    y: extents.top(),
+    width: extents.width(),
+    height: extents.height(),
+    focused: wm.window_manager.window_has_focus(window),
+    floating: workspace
+      .as_ref()
+      .map_or(false, |workspace| workspace.is_floating(window)),
+    fullscreen: workspace.map_or(false, |workspace| {
+      workspace.fullscreen_window().as_ref() == Some(window)
+    }),
+    scratchpad: wm.scratchpad.contains(window),
+  }
+}
@@ -0,0 +1,172 @@
+use crate::shortcuts::ActionShortcut;
+use serde::{Deserialize, Serialize};
+use wlral::input::keyboard::KeyboardConfig;
+
+/// The environment variable that holds the path to the IPC socket
+pub(crate) const SOCKET_ENV: &str = "CASCADE_SOCK";
+
+/// A request sent by a client as a single line of JSON.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "command")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum IpcRequest {
+  Action(ActionShortcut),
+  GetOutputs,
+  GetWorkspaces,
+  GetWindows,
+  GetFocusedWindow,
+  /// The layout of the keyboard that was last typed on
+  GetKeyboardLayout,
+  GetKeyboardMode,
+  /// Turns the connection into a stream of [`IpcEvent`]s
+  Subscribe,
+}
+
+/// The answer to an [`IpcRequest`], sent back as a single line of JSON.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum IpcResponse {
+  Success,
+  Error(String),
+  Outputs(Vec<OutputInfo>),
+  Workspaces(Vec<WorkspaceInfo>),
+  Windows(Vec<WindowInfo>),
+  FocusedWindow(Option<WindowInfo>),
+  KeyboardLayout(KeyboardConfig),
+  /// The active keyboard mode, or None if no mode is active
+  KeyboardMode(Option<String>),
+}
+
+/// Sent to subscribed clients, one event per line of JSON.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum IpcEvent {
+  WindowOpened {
+    window: WindowInfo,
+  },
+  WindowClosed {
+    window: WindowInfo,
+  },
+  WindowFocused {
+    window: WindowInfo,
+  },
+  WorkspaceFocused {
+    workspace: WorkspaceInfo,
+    workspaces: Vec<WorkspaceInfo>,
+  },
+  OutputAdded {
+    output: OutputInfo,
+  },
+  OutputRemoved {
+    output: OutputInfo,
+  },
+  KeyboardLayoutSwitched {
+    layout: KeyboardConfig,
+    /// The device that switched layout, or None if all devices using
+    /// keyboard_layouts switched
+    device: Option<String>,
+  },
+  KeyboardModeChanged {
+    mode: Option<String>,
+  },
+  ConfigReloaded,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub(crate) struct OutputInfo {
+  pub(crate) name: String,
+  pub(crate) x: i32,
+  pub(crate) y: i32,
+  pub(crate) width: i32,
+  pub(crate) height: i32,
+  pub(crate) workspace: Option<usize>,
+  pub(crate) focused: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub(crate) struct WorkspaceInfo {
+  pub(crate) id: usize,
+  /// The stable, 1-based index used by FocusWorkspace and window rules
+  pub(crate) index: usize,
+  pub(crate) name: Option<String>,
+  pub(crate) output: Option<String>,
+  pub(crate) focused: bool,
+  pub(crate) scroll_left: i32,
+  /// Window ids in the order they are laid out
+  pub(crate) windows: Vec<usize>,
+  /// Window ids of each column, from top to bottom
+  pub(crate) columns: Vec<Vec<usize>>,
+  pub(crate) floating_windows: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub(crate) struct WindowInfo {
+  pub(crate) id: usize,
+  pub(crate) title: Option<String>,
+  pub(crate) app_id: Option<String>,
+  pub(crate) workspace: Option<usize>,
+  pub(crate) x: i32,
+  pub(crate) y: i32,
+  pub(crate) width: i32,
+  pub(crate) height: i32,
+  pub(crate) focused: bool,
+  pub(crate) floating: bool,
+  pub(crate) fullscreen: bool,
+  /// If the window is in the scratchpad, shown or hidden
+  pub(crate) scratchpad: bool,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shortcuts::{Direction, WorkspaceSelector};
+  use serde_json::{json, Value};
+
+  fn assert_round_trip(request: IpcRequest, expected: Value) {
+    assert_eq!(serde_json::to_value(&request).unwrap(), expected);
+    assert_eq!(
+      serde_json::from_value::<IpcRequest>(expected).unwrap(),
+      request
+    );
+  }
+
+  #[test]
+  fn requests_are_tagged_by_command() {
+    assert_round_trip(IpcRequest::GetWindows, json!({ "command": "get_windows" }));
+    assert_round_trip(
+      IpcRequest::GetKeyboardLayout,
+      json!({ "command": "get_keyboard_layout" }),
+    );
+  }
+
+  #[test]
+  fn actions_are_tagged_inside_the_request() {
+    assert_round_trip(
+      IpcRequest::Action(ActionShortcut::CloseWindow),
+      json!({ "command": "action", "action": "close_window" }),
+    );
+    assert_round_trip(
+      IpcRequest::Action(ActionShortcut::Navigate {
+        direction: Direction::Left,
+      }),
+      json!({ "command": "action", "action": "navigate", "direction": "Left" }),
+    );
+  }
+
+  #[test]
+  fn workspace_selectors_are_flattened_into_actions() {
+    assert_round_trip(
+      IpcRequest::Action(ActionShortcut::FocusWorkspace {
+        workspace: WorkspaceSelector::Index(2),
+      }),
+      json!({ "command": "action", "action": "focus_workspace", "index": 2 }),
+    );
+    assert_round_trip(
+      IpcRequest::Action(ActionShortcut::MoveWindowToWorkspace {
+        workspace: WorkspaceSelector::Name("web".to_string()),
+      }),
+      json!({ "command": "action", "action": "move_window_to_workspace", "name": "web" }),
+    );
+  }
+}
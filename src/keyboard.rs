@@ -1,34 +1,23 @@
 use crate::actions::*;
-use crate::{entities::workspace::WorkspacePosition, window_manager::CascadeWindowManager};
-use log::{debug, error, trace};
+pub(crate) use crate::shortcuts::ActionShortcut;
+use crate::{
+  config::Config, entities::workspace::WorkspacePosition, ipc::protocol::IpcEvent,
+  keyboard_devices::switch_device_keyboard_layout, shortcuts::LayoutScope,
+  window_manager::CascadeWindowManager,
+};
+use log::{debug, error, trace, warn};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::{collections::BTreeMap, process::Command};
+use std::{
+  cell::{Cell, RefCell},
+  collections::BTreeMap,
+  error::Error,
+  fmt,
+  process::Command,
+  time::{Duration, Instant},
+};
 use wlral::input::events::*;
 use xkbcommon::xkb;
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-#[serde(tag = "action")]
-#[serde(rename_all = "snake_case")]
-pub(crate) enum ActionShortcut {
-  NavigateToFirst,
-  NavigateToLast,
-  Navigate { direction: Direction },
-  NavigateWorkspace { direction: VerticalDirection },
-  NavigateMonitor { direction: Direction },
-
-  MoveWindow { direction: Direction },
-  MoveWindowWorkspace { direction: VerticalDirection },
-  MoveWindowMonitor { direction: Direction },
-
-  ResizeWindow { steps: Vec<f32> },
-  CenterWindow,
-  CloseWindow,
-
-  SwitchKeyboardLayout,
-
-  DebugPrintWindows,
-}
-
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub(crate) struct CommandShortcut {
   cmd: String,
@@ -42,24 +31,147 @@ pub(crate) enum KeyboardShortcut {
   Command(CommandShortcut),
 }
 
+/// When a keybinding triggers its shortcut
+#[derive(Debug, Default, Ord, PartialOrd, Eq, PartialEq, Copy, Clone)]
+pub(crate) enum KeyTrigger {
+  #[default]
+  Press,
+  Release,
+  /// Released before the hold timeout without pressing any other key
+  Tap,
+  /// Held for the hold timeout without pressing any other key
+  Hold,
+}
+
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone)]
+pub(crate) enum Key {
+  /// A key as mapped by the active keyboard layout
+  Sym(xkb::Keysym),
+  /// A physical key by its xkb keycode, which is the same in every layout
+  Code(u32),
+}
+
+impl Default for Key {
+  fn default() -> Self {
+    Key::Sym(xkb::KEY_NoSymbol)
+  }
+}
+
+impl Key {
+  fn is_modifier(&self) -> bool {
+    match self {
+      Key::Sym(key) => is_modifier_key(*key),
+      Key::Code(_) => false,
+    }
+  }
+}
+
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Modifier {
+  #[serde(alias = "mod1")]
+  Alt,
+  #[serde(alias = "control")]
+  Ctrl,
+  Shift,
+  #[serde(alias = "logo", alias = "mod4")]
+  Super,
+  /// Mod3, which Hyper is often mapped to
+  #[serde(alias = "hyper")]
+  Mod3,
+  /// Mod5, which AltGr is usually mapped to
+  #[serde(alias = "altgr")]
+  Mod5,
+  #[serde(alias = "lock")]
+  CapsLock,
+  #[serde(alias = "mod2")]
+  NumLock,
+}
+
+impl Modifier {
+  const ALL: [Modifier; 8] = [
+    Modifier::Alt,
+    Modifier::Ctrl,
+    Modifier::Shift,
+    Modifier::Super,
+    Modifier::Mod3,
+    Modifier::Mod5,
+    Modifier::CapsLock,
+    Modifier::NumLock,
+  ];
+
+  fn name(self) -> &'static str {
+    match self {
+      Modifier::Alt => "alt",
+      Modifier::Ctrl => "ctrl",
+      Modifier::Shift => "shift",
+      Modifier::Super => "super",
+      Modifier::Mod3 => "mod3",
+      Modifier::Mod5 => "mod5",
+      Modifier::CapsLock => "caps_lock",
+      Modifier::NumLock => "num_lock",
+    }
+  }
+
+  fn from_name(name: &str) -> Option<Modifier> {
+    match &name.to_ascii_lowercase() as &str {
+      "alt" | "mod1" => Some(Modifier::Alt),
+      "ctrl" | "control" => Some(Modifier::Ctrl),
+      "shift" => Some(Modifier::Shift),
+      "super" | "logo" | "mod4" => Some(Modifier::Super),
+      "mod3" | "hyper" => Some(Modifier::Mod3),
+      "mod5" | "altgr" => Some(Modifier::Mod5),
+      "caps_lock" | "lock" => Some(Modifier::CapsLock),
+      "num_lock" | "mod2" => Some(Modifier::NumLock),
+      _ => None,
+    }
+  }
+
+  fn xkb_name(self) -> &'static str {
+    match self {
+      Modifier::Alt => xkb::MOD_NAME_ALT,
+      Modifier::Ctrl => xkb::MOD_NAME_CTRL,
+      Modifier::Shift => xkb::MOD_NAME_SHIFT,
+      Modifier::Super => xkb::MOD_NAME_LOGO,
+      Modifier::Mod3 => "Mod3",
+      Modifier::Mod5 => "Mod5",
+      Modifier::CapsLock => xkb::MOD_NAME_CAPS,
+      Modifier::NumLock => xkb::MOD_NAME_NUM,
+    }
+  }
+}
+
+/// Lock modifiers are ignored by default so that they never change which
+/// shortcut a key triggers
+const DEFAULT_IGNORED_MODIFIERS: [Modifier; 2] = [Modifier::CapsLock, Modifier::NumLock];
+
 #[derive(Default, Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
 pub(crate) struct Keybinding {
   alt: bool,
   ctrl: bool,
   logo: bool,
   shift: bool,
-  key: xkb::Keysym,
+  mod3: bool,
+  mod5: bool,
+  caps_lock: bool,
+  num_lock: bool,
+  key: Key,
+  trigger: KeyTrigger,
 }
 
+/// One or more keybindings that have to be pressed in order
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
+pub(crate) struct KeySequence(Vec<Keybinding>);
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-pub(crate) struct KeyboardShortcutsConfig(BTreeMap<Keybinding, KeyboardShortcut>);
+pub(crate) struct KeyboardShortcutsConfig(BTreeMap<KeySequence, KeyboardShortcut>);
 
 impl Default for KeyboardShortcutsConfig {
   fn default() -> Self {
     let mut default = BTreeMap::new();
     default.insert(
       Keybinding {
-        key: xkb::KEY_Home,
+        key: Key::Sym(xkb::KEY_Home),
         logo: true,
         ..Keybinding::default()
       },
@@ -67,7 +179,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_End,
+        key: Key::Sym(xkb::KEY_End),
         logo: true,
         ..Keybinding::default()
       },
@@ -75,7 +187,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Left,
+        key: Key::Sym(xkb::KEY_Left),
         logo: true,
         ..Keybinding::default()
       },
@@ -85,7 +197,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Right,
+        key: Key::Sym(xkb::KEY_Right),
         logo: true,
         ..Keybinding::default()
       },
@@ -95,7 +207,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Up,
+        key: Key::Sym(xkb::KEY_Up),
         logo: true,
         ..Keybinding::default()
       },
@@ -105,7 +217,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Down,
+        key: Key::Sym(xkb::KEY_Down),
         logo: true,
         ..Keybinding::default()
       },
@@ -115,7 +227,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Left,
+        key: Key::Sym(xkb::KEY_Left),
         alt: true,
         logo: true,
         ..Keybinding::default()
@@ -126,7 +238,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Right,
+        key: Key::Sym(xkb::KEY_Right),
         alt: true,
         logo: true,
         ..Keybinding::default()
@@ -138,7 +250,7 @@ impl Default for KeyboardShortcutsConfig {
 
     default.insert(
       Keybinding {
-        key: xkb::KEY_Left,
+        key: Key::Sym(xkb::KEY_Left),
         ctrl: true,
         logo: true,
         ..Keybinding::default()
@@ -149,7 +261,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Right,
+        key: Key::Sym(xkb::KEY_Right),
         ctrl: true,
         logo: true,
         ..Keybinding::default()
@@ -160,7 +272,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Up,
+        key: Key::Sym(xkb::KEY_Up),
         ctrl: true,
         logo: true,
         ..Keybinding::default()
@@ -171,7 +283,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Down,
+        key: Key::Sym(xkb::KEY_Down),
         ctrl: true,
         logo: true,
         ..Keybinding::default()
@@ -182,7 +294,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Left,
+        key: Key::Sym(xkb::KEY_Left),
         alt: true,
         ctrl: true,
         logo: true,
@@ -194,7 +306,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_Right,
+        key: Key::Sym(xkb::KEY_Right),
         alt: true,
         ctrl: true,
         logo: true,
@@ -207,7 +319,72 @@ impl Default for KeyboardShortcutsConfig {
 
     default.insert(
       Keybinding {
-        key: xkb::KEY_r,
+        key: Key::Sym(xkb::KEY_Up),
+        logo: true,
+        shift: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::Navigate {
+        direction: Direction::Up,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_Down),
+        logo: true,
+        shift: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::Navigate {
+        direction: Direction::Down,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_Up),
+        ctrl: true,
+        logo: true,
+        shift: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::MoveWindow {
+        direction: Direction::Up,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_Down),
+        ctrl: true,
+        logo: true,
+        shift: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::MoveWindow {
+        direction: Direction::Down,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_i),
+        logo: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::PullWindow {
+        direction: Direction::Right,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_o),
+        logo: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::ExpelWindow),
+    );
+
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_r),
         logo: true,
         ..Keybinding::default()
       },
@@ -217,7 +394,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_f,
+        key: Key::Sym(xkb::KEY_f),
         logo: true,
         ..Keybinding::default()
       },
@@ -225,7 +402,16 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_c,
+        key: Key::Sym(xkb::KEY_f),
+        logo: true,
+        shift: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::ToggleFullscreen),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_c),
         logo: true,
         ..Keybinding::default()
       },
@@ -233,7 +419,7 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_BackSpace,
+        key: Key::Sym(xkb::KEY_BackSpace),
         logo: true,
         ..Keybinding::default()
       },
@@ -241,86 +427,425 @@ impl Default for KeyboardShortcutsConfig {
     );
     default.insert(
       Keybinding {
-        key: xkb::KEY_space,
+        key: Key::Sym(xkb::KEY_space),
         logo: true,
+        shift: true,
         ..Keybinding::default()
       },
-      KeyboardShortcut::Action(ActionShortcut::SwitchKeyboardLayout),
+      KeyboardShortcut::Action(ActionShortcut::ToggleFloating),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_space),
+        logo: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::SwitchKeyboardLayout {
+        scope: LayoutScope::All,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_Tab),
+        alt: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::CycleWindows {
+        scope: CycleScope::All,
+        direction: CycleDirection::Next,
+      }),
+    );
+    default.insert(
+      Keybinding {
+        key: Key::Sym(xkb::KEY_Tab),
+        alt: true,
+        shift: true,
+        ..Keybinding::default()
+      },
+      KeyboardShortcut::Action(ActionShortcut::CycleWindows {
+        scope: CycleScope::All,
+        direction: CycleDirection::Previous,
+      }),
     );
 
-    KeyboardShortcutsConfig(default)
+    let number_keys = [
+      xkb::KEY_1,
+      xkb::KEY_2,
+      xkb::KEY_3,
+      xkb::KEY_4,
+      xkb::KEY_5,
+      xkb::KEY_6,
+      xkb::KEY_7,
+      xkb::KEY_8,
+      xkb::KEY_9,
+    ];
+    for (i, key) in number_keys.iter().enumerate() {
+      default.insert(
+        Keybinding {
+          key: Key::Sym(*key),
+          logo: true,
+          ..Keybinding::default()
+        },
+        KeyboardShortcut::Action(ActionShortcut::FocusWorkspace {
+          workspace: WorkspaceSelector::Index(i + 1),
+        }),
+      );
+      default.insert(
+        Keybinding {
+          key: Key::Sym(*key),
+          logo: true,
+          shift: true,
+          ..Keybinding::default()
+        },
+        KeyboardShortcut::Action(ActionShortcut::MoveWindowToWorkspace {
+          workspace: WorkspaceSelector::Index(i + 1),
+        }),
+      );
+    }
+
+    KeyboardShortcutsConfig(
+      default
+        .into_iter()
+        .map(|(binding, shortcut)| (KeySequence(vec![binding]), shortcut))
+        .collect(),
+    )
   }
 }
 
-impl Serialize for Keybinding {
-  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-  where
-    S: Serializer,
-  {
-    let mut keys = vec![];
-    if self.alt {
-      keys.push("alt");
+impl KeyboardShortcutsConfig {
+  pub(crate) fn get(&self, sequence: &[Keybinding]) -> Option<&KeyboardShortcut> {
+    self.0.get(&KeySequence(sequence.to_vec()))
+  }
+
+  /// If the key has any release, tap or hold shortcuts
+  fn has_release_shortcuts(&self, binding: &Keybinding) -> bool {
+    [KeyTrigger::Release, KeyTrigger::Tap, KeyTrigger::Hold]
+      .iter()
+      .any(|trigger| self.get(&[binding.with_trigger(*trigger)]).is_some())
+  }
+
+  /// If there are longer sequences that start with the keys
+  pub(crate) fn has_prefix(&self, sequence: &[Keybinding]) -> bool {
+    self
+      .0
+      .keys()
+      .any(|keys| keys.0.len() > sequence.len() && keys.0.starts_with(sequence))
+  }
+
+  pub(crate) fn validate(config: &Config) -> Result<(), Box<dyn Error>> {
+    let sections = std::iter::once(("keyboard_shortcuts".to_string(), &config.keyboard_shortcuts))
+      .chain(
+        config
+          .keyboard_modes
+          .iter()
+          .map(|(name, shortcuts)| (format!("keyboard_modes.{}", name), shortcuts)),
+      );
+    let ignored_modifiers = config
+      .ignored_modifiers
+      .clone()
+      .unwrap_or_else(|| DEFAULT_IGNORED_MODIFIERS.to_vec());
+    for (section, shortcuts) in sections {
+      for (sequence, shortcut) in shortcuts.0.iter() {
+        for binding in sequence.0.iter() {
+          if let Some(modifier) = ignored_modifiers
+            .iter()
+            .find(|modifier| binding.modifier(**modifier))
+          {
+            return Err(
+              format!(
+                "{}: \"{}\" uses the ignored modifier {}",
+                section,
+                sequence,
+                modifier.name()
+              )
+              .into(),
+            );
+          }
+        }
+        if sequence.0.len() > 1
+          && sequence
+            .0
+            .iter()
+            .any(|binding| binding.trigger != KeyTrigger::Press)
+        {
+          return Err(
+            format!(
+              "{}: \"{}\" release, tap and hold can not be used in key sequences",
+              section, sequence
+            )
+            .into(),
+          );
+        }
+        match shortcut {
+          KeyboardShortcut::Action(ActionShortcut::EnterMode { name }) => {
+            if !config.keyboard_modes.contains_key(name) {
+              return Err(format!("{}: Unknown mode \"{}\"", section, name).into());
+            }
+          }
+          KeyboardShortcut::Action(ActionShortcut::ResizeWindow { steps }) => {
+            if steps.iter().any(|step| *step <= 0.0 || *step > 1.0) {
+              return Err(
+                format!(
+                  "{}: \"{}\" resize steps must be between 0 and 1",
+                  section, sequence
+                )
+                .into(),
+              );
+            }
+          }
+          _ => {}
+        }
+      }
     }
-    if self.ctrl {
-      keys.push("ctrl");
+
+    Ok(())
+  }
+}
+
+/// How long to wait for the next key of a sequence, in milliseconds
+const DEFAULT_SEQUENCE_TIMEOUT: u64 = 1000;
+/// How long a key must be held to not count as a tap, in milliseconds
+const DEFAULT_HOLD_TIMEOUT: u64 = 300;
+
+/// A key with release, tap or hold shortcuts that has not been released yet
+#[derive(Clone, Debug)]
+struct HeldKey {
+  keycode: u32,
+  time_msec: u32,
+  /// The binding as it was when the key was pressed
+  binding: Keybinding,
+  pressed_at: Instant,
+  /// If another key was pressed while this key was held
+  interrupted: bool,
+  /// If the hold shortcut has been triggered
+  held: bool,
+  /// If the press was not passed on to the client, so the release should not be either
+  swallowed: bool,
+  /// If the press was only held back in case a tap or hold shortcut is
+  /// triggered, and should be passed on to the client if none is
+  replay: bool,
+}
+
+impl HeldKey {
+  /// Stops holding back the press, returning the key if it should be passed
+  /// on to the client
+  fn take_replay(&mut self) -> Option<HeldKey> {
+    if !self.replay {
+      return None;
     }
-    if self.shift {
-      keys.push("shift");
+    self.replay = false;
+    self.swallowed = false;
+    Some(self.clone())
+  }
+}
+
+/// Keyboard state that lasts between key presses.
+#[derive(Default, Debug)]
+pub(crate) struct KeyboardState {
+  mode: RefCell<Option<String>>,
+  /// Keys pressed so far of a sequence that has not completed yet
+  pending_sequence: RefCell<Vec<Keybinding>>,
+  pending_since: Cell<Option<Instant>>,
+  held_keys: RefCell<Vec<HeldKey>>,
+  /// If any modifier was held at the last key event
+  modifiers_held: Cell<bool>,
+}
+
+impl KeyboardState {
+  /// The name of the active mode, or None if keyboard_shortcuts is used
+  pub(crate) fn mode(&self) -> Option<String> {
+    self.mode.borrow().clone()
+  }
+
+  pub(crate) fn modifiers_held(&self) -> bool {
+    self.modifiers_held.get()
+  }
+
+  pub(crate) fn reset_sequence(&self) {
+    self.pending_sequence.borrow_mut().clear();
+    self.pending_since.set(None);
+  }
+}
+
+pub(crate) fn enter_mode(wm: &CascadeWindowManager, name: &str) {
+  if !wm.config().keyboard_modes.contains_key(name) {
+    warn!("Can not enter unknown mode \"{}\"", name);
+    return;
+  }
+  debug!("Entering mode \"{}\"", name);
+  set_mode(wm, Some(name.to_string()));
+}
+
+pub(crate) fn exit_mode(wm: &CascadeWindowManager) {
+  debug!("Exiting mode");
+  set_mode(wm, None);
+}
+
+fn set_mode(wm: &CascadeWindowManager, mode: Option<String>) {
+  wm.keyboard.reset_sequence();
+  if *wm.keyboard.mode.borrow() != mode {
+    *wm.keyboard.mode.borrow_mut() = mode;
+    wm.broadcast_ipc_event(|| IpcEvent::KeyboardModeChanged {
+      mode: wm.keyboard.mode(),
+    });
+  }
+}
+
+impl Keybinding {
+  fn with_trigger(&self, trigger: KeyTrigger) -> Keybinding {
+    Keybinding {
+      trigger,
+      ..self.clone()
     }
-    if self.logo {
-      keys.push("super");
+  }
+
+  fn with_key(&self, key: Key) -> Keybinding {
+    Keybinding {
+      key,
+      ..self.clone()
     }
-    let key = xkb::keysym_get_name(self.key);
-    keys.push(&key);
-    keys.join("+").serialize(serializer)
   }
-}
 
-impl<'de> Deserialize<'de> for Keybinding {
-  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-  where
-    D: Deserializer<'de>,
-  {
-    use serde::de::Error;
-    let keys: String = Deserialize::deserialize(deserializer)?;
+  fn parse(keys: &str) -> Result<Keybinding, String> {
+    let (keys, trigger) = match keys
+      .rfind(':')
+      .map(|index| (&keys[..index], &keys[index + 1..]))
+    {
+      Some((keys, "press")) => (keys, KeyTrigger::Press),
+      Some((keys, "release")) => (keys, KeyTrigger::Release),
+      Some((keys, "tap")) => (keys, KeyTrigger::Tap),
+      Some((keys, "hold")) => (keys, KeyTrigger::Hold),
+      _ => (keys, KeyTrigger::Press),
+    };
     let mut keys = keys.split("+").map(str::trim).collect::<Vec<_>>();
-    let key = keys.pop().ok_or(Error::custom("No key specified"))?;
+    let key = keys.pop().ok_or("No key specified")?;
     let mods = keys;
+    let key = if let Some(code) = key.strip_prefix("code:") {
+      code
+        .trim()
+        .parse()
+        .map(Key::Code)
+        .map_err(|_| format!("Invalid key code \"{}\" specified", code))?
+    } else {
+      match xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE) {
+        xkb::KEY_NoSymbol => return Err(format!("Invalid key \"{}\" specified", key)),
+        keysym => Key::Sym(keysym),
+      }
+    };
     let mut binding = Keybinding {
-      key: xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE),
+      key,
+      trigger,
       ..Keybinding::default()
     };
-    if binding.key == xkb::KEY_NoSymbol {
-      return Err(Error::custom(format!("Invalid key \"{}\" specified", key)));
-    }
     for modifier in mods {
-      match &modifier.to_ascii_lowercase() as &str {
-        "alt" => {
-          binding.alt = true;
-        }
-        "ctrl" => {
-          binding.ctrl = true;
-        }
-        "shift" => {
-          binding.shift = true;
-        }
-        "super" | "logo" => {
-          binding.logo = true;
-        }
-        _ => {
-          return Err(Error::custom(format!(
-            "Invalid modifier \"{}\" specified",
-            modifier
-          )));
+      match Modifier::from_name(modifier) {
+        Some(modifier) => binding.set_modifier(modifier, true),
+        None => {
+          return Err(format!("Invalid modifier \"{}\" specified", modifier));
         }
       }
     }
     Ok(binding)
   }
+
+  /// If any modifier other than the lock modifiers is active
+  fn has_held_modifiers(&self) -> bool {
+    Modifier::ALL
+      .iter()
+      .filter(|modifier| **modifier != Modifier::CapsLock && **modifier != Modifier::NumLock)
+      .any(|modifier| self.modifier(*modifier))
+  }
+
+  fn modifier(&self, modifier: Modifier) -> bool {
+    match modifier {
+      Modifier::Alt => self.alt,
+      Modifier::Ctrl => self.ctrl,
+      Modifier::Shift => self.shift,
+      Modifier::Super => self.logo,
+      Modifier::Mod3 => self.mod3,
+      Modifier::Mod5 => self.mod5,
+      Modifier::CapsLock => self.caps_lock,
+      Modifier::NumLock => self.num_lock,
+    }
+  }
+
+  fn set_modifier(&mut self, modifier: Modifier, active: bool) {
+    match modifier {
+      Modifier::Alt => self.alt = active,
+      Modifier::Ctrl => self.ctrl = active,
+      Modifier::Shift => self.shift = active,
+      Modifier::Super => self.logo = active,
+      Modifier::Mod3 => self.mod3 = active,
+      Modifier::Mod5 => self.mod5 = active,
+      Modifier::CapsLock => self.caps_lock = active,
+      Modifier::NumLock => self.num_lock = active,
+    }
+  }
+}
+
+impl fmt::Display for Keybinding {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut keys = Modifier::ALL
+      .iter()
+      .filter(|modifier| self.modifier(**modifier))
+      .map(|modifier| modifier.name())
+      .collect::<Vec<_>>();
+    let key = match self.key {
+      Key::Sym(keysym) => xkb::keysym_get_name(keysym),
+      Key::Code(keycode) => format!("code:{}", keycode),
+    };
+    keys.push(&key);
+    write!(f, "{}", keys.join("+"))?;
+    match self.trigger {
+      KeyTrigger::Press => Ok(()),
+      KeyTrigger::Release => write!(f, ":release"),
+      KeyTrigger::Tap => write!(f, ":tap"),
+      KeyTrigger::Hold => write!(f, ":hold"),
+    }
+  }
+}
+
+impl fmt::Display for KeySequence {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let bindings = self.0.iter().map(Keybinding::to_string).collect::<Vec<_>>();
+    write!(f, "{}", bindings.join(", "))
+  }
+}
+
+impl Serialize for KeySequence {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    self.to_string().serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for KeySequence {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    use serde::de::Error;
+    let keys: String = Deserialize::deserialize(deserializer)?;
+    // Allow spaces around + so that only spaces and commas separate the keys
+    // of the sequence
+    let keys = keys.split("+").map(str::trim).collect::<Vec<_>>().join("+");
+    let bindings = keys
+      .split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|keys| !keys.is_empty())
+      .map(Keybinding::parse)
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(Error::custom)?;
+    if bindings.is_empty() {
+      return Err(Error::custom("No key specified"));
+    }
+    Ok(KeySequence(bindings))
+  }
 }
 
 impl ActionShortcut {
-  fn triggered(&self, wm: &CascadeWindowManager) {
+  pub(crate) fn triggered(&self, wm: &CascadeWindowManager) {
     match self {
       ActionShortcut::NavigateToFirst => {
         navigate_first(wm);
@@ -337,6 +862,12 @@ impl ActionShortcut {
       ActionShortcut::NavigateMonitor { direction } => {
         navigate_monitor(wm, *direction, WorkspacePosition::ActiveWindow);
       }
+      ActionShortcut::FocusWorkspace { workspace } => {
+        navigate_to_workspace(wm, workspace);
+      }
+      ActionShortcut::CycleWindows { scope, direction } => {
+        cycle_windows(wm, *scope, *direction);
+      }
       ActionShortcut::MoveWindow { direction } => {
         move_window(wm, *direction);
       }
@@ -346,6 +877,15 @@ impl ActionShortcut {
       ActionShortcut::MoveWindowMonitor { direction } => {
         move_window_monitor(wm, *direction, WorkspacePosition::ActiveWindow);
       }
+      ActionShortcut::MoveWindowToWorkspace { workspace } => {
+        move_window_to_workspace(wm, workspace);
+      }
+      ActionShortcut::PullWindow { direction } => {
+        pull_window(wm, *direction);
+      }
+      ActionShortcut::ExpelWindow => {
+        expel_window(wm);
+      }
       ActionShortcut::ResizeWindow { steps } => {
         resize_active_window(wm, steps);
       }
@@ -357,8 +897,38 @@ impl ActionShortcut {
           active_window.ask_client_to_close();
         }
       }
-      ActionShortcut::SwitchKeyboardLayout => {
-        switch_keyboard_layout(wm);
+      ActionShortcut::ToggleFloating => {
+        toggle_floating(wm);
+      }
+      ActionShortcut::ToggleFullscreen => {
+        toggle_fullscreen(wm);
+      }
+      ActionShortcut::MoveToScratchpad { name } => {
+        move_to_scratchpad(wm, name.clone());
+      }
+      ActionShortcut::ToggleScratchpad { name, app_id } => {
+        toggle_scratchpad(wm, name.as_deref(), app_id.as_deref());
+      }
+      ActionShortcut::SwitchKeyboardLayout { scope } => {
+        let device_name = match scope {
+          LayoutScope::All => None,
+          LayoutScope::Device => wm.keyboard_devices.last_device(),
+        };
+        let switched = device_name.map_or(false, |device_name| {
+          switch_device_keyboard_layout(wm, &device_name)
+        });
+        if !switched {
+          switch_keyboard_layout(wm);
+        }
+      }
+      ActionShortcut::EnterMode { name } => {
+        enter_mode(wm, name);
+      }
+      ActionShortcut::ExitMode => {
+        exit_mode(wm);
+      }
+      ActionShortcut::ReloadConfig => {
+        wm.reload_config();
       }
       ActionShortcut::DebugPrintWindows => {
         // println!("DEBUG: Windows: {:?}", &wm.mru_windows());
@@ -390,41 +960,434 @@ impl KeyboardShortcut {
   }
 }
 
-pub(crate) fn handle_key_press(wm: &CascadeWindowManager, event: &KeyboardEvent) -> bool {
-  if event.state() == KeyState::Pressed {
-    let binding = Keybinding {
-      alt: event
-        .xkb_state()
-        .mod_name_is_active(xkb::MOD_NAME_ALT, xkb::STATE_MODS_DEPRESSED),
-      ctrl: event
-        .xkb_state()
-        .mod_name_is_active(xkb::MOD_NAME_CTRL, xkb::STATE_MODS_DEPRESSED),
-      logo: event
-        .xkb_state()
-        .mod_name_is_active(xkb::MOD_NAME_LOGO, xkb::STATE_MODS_DEPRESSED),
-      shift: event
-        .xkb_state()
-        .mod_name_is_active(xkb::MOD_NAME_SHIFT, xkb::STATE_MODS_DEPRESSED),
-      key: xkb::keysym_from_name(
-        &xkb::keysym_get_name(event.get_one_sym()),
-        xkb::KEYSYM_CASE_INSENSITIVE,
-      ),
-    };
-    let shortcut = wm.config.keyboard_shortcuts.0.get(&binding).cloned();
+fn is_modifier_key(key: xkb::Keysym) -> bool {
+  match key {
+    xkb::KEY_Shift_L
+    | xkb::KEY_Shift_R
+    | xkb::KEY_Control_L
+    | xkb::KEY_Control_R
+    | xkb::KEY_Alt_L
+    | xkb::KEY_Alt_R
+    | xkb::KEY_Meta_L
+    | xkb::KEY_Meta_R
+    | xkb::KEY_Super_L
+    | xkb::KEY_Super_R
+    | xkb::KEY_Hyper_L
+    | xkb::KEY_Hyper_R
+    | xkb::KEY_ISO_Level3_Shift => true,
+    _ => false,
+  }
+}
 
-    trace!(
-      "Pressed key {}, binding: {:?}",
-      xkb::keysym_get_name(event.get_one_sym()),
-      &binding
+fn binding_for_event(wm: &CascadeWindowManager, event: &KeyboardEvent) -> Keybinding {
+  let xkb_state = event.xkb_state();
+  let key = xkb::keysym_from_name(
+    &xkb::keysym_get_name(event.get_one_sym()),
+    xkb::KEYSYM_CASE_INSENSITIVE,
+  );
+  let mut binding = Keybinding {
+    key: Key::Sym(key),
+    ..Keybinding::default()
+  };
+  // Effective modifiers include locked modifiers, such as Caps Lock
+  for modifier in Modifier::ALL.iter() {
+    binding.set_modifier(
+      *modifier,
+      xkb_state.mod_name_is_active(modifier.xkb_name(), xkb::STATE_MODS_EFFECTIVE),
     );
-    if let Some(shortcut) = shortcut {
-      debug!("Triggering shortcut");
-      shortcut.triggered(wm);
-      true
-    } else {
-      false
+  }
+  for modifier in ignored_modifiers(wm).iter() {
+    binding.set_modifier(*modifier, false);
+  }
+  // A modifier key does not modify itself, so that e.g. Super_L:tap matches
+  // the same binding on press and release
+  let own_modifier = match key {
+    xkb::KEY_Alt_L | xkb::KEY_Alt_R | xkb::KEY_Meta_L | xkb::KEY_Meta_R => Some(Modifier::Alt),
+    xkb::KEY_Control_L | xkb::KEY_Control_R => Some(Modifier::Ctrl),
+    xkb::KEY_Super_L | xkb::KEY_Super_R => Some(Modifier::Super),
+    xkb::KEY_Shift_L | xkb::KEY_Shift_R => Some(Modifier::Shift),
+    xkb::KEY_ISO_Level3_Shift => Some(Modifier::Mod5),
+    xkb::KEY_Caps_Lock => Some(Modifier::CapsLock),
+    xkb::KEY_Num_Lock => Some(Modifier::NumLock),
+    _ => None,
+  };
+  if let Some(modifier) = own_modifier {
+    binding.set_modifier(modifier, false);
+  }
+  binding
+}
+
+/// The key on the first shift level, so that e.g. super+shift+1 matches
+/// even though shift turns 1 into exclam, and mod5+e even though AltGr turns e
+/// into EuroSign
+fn base_level_key(event: &KeyboardEvent) -> Key {
+  let xkb_state = event.xkb_state();
+  let keycode = event.xkb_keycode();
+  let layout = xkb_state.key_get_layout(keycode);
+  let keysym = xkb_state
+    .get_keymap()
+    .key_get_syms_by_level(keycode, layout, 0)
+    .first()
+    .copied()
+    .unwrap_or(xkb::KEY_NoSymbol);
+  Key::Sym(keysym)
+}
+
+fn ignored_modifiers(wm: &CascadeWindowManager) -> Vec<Modifier> {
+  wm.config()
+    .ignored_modifiers
+    .clone()
+    .unwrap_or_else(|| DEFAULT_IGNORED_MODIFIERS.to_vec())
+}
+
+/// Looks up the keys in the shortcuts of the active mode.
+///
+/// Returns the matching shortcut and if there are longer sequences starting
+/// with the keys.
+fn active_shortcut(
+  wm: &CascadeWindowManager,
+  sequence: &[Keybinding],
+) -> (Option<KeyboardShortcut>, bool) {
+  with_active_shortcuts(wm, |shortcuts| {
+    (
+      shortcuts.get(sequence).cloned(),
+      shortcuts.has_prefix(sequence),
+    )
+  })
+  .unwrap_or((None, false))
+}
+
+fn has_release_shortcuts(wm: &CascadeWindowManager, binding: &Keybinding) -> bool {
+  with_active_shortcuts(wm, |shortcuts| shortcuts.has_release_shortcuts(binding)).unwrap_or(false)
+}
+
+fn with_active_shortcuts<T>(
+  wm: &CascadeWindowManager,
+  f: impl FnOnce(&KeyboardShortcutsConfig) -> T,
+) -> Option<T> {
+  let config = wm.config();
+  let shortcuts = match wm.keyboard.mode() {
+    Some(ref mode) => config.keyboard_modes.get(mode),
+    None => Some(&config.keyboard_shortcuts),
+  };
+  shortcuts.map(f)
+}
+
+fn trigger_binding(wm: &CascadeWindowManager, binding: Keybinding) -> bool {
+  let (shortcut, _) = active_shortcut(wm, &[binding]);
+  if let Some(shortcut) = shortcut {
+    debug!("Triggering shortcut");
+    shortcut.triggered(wm);
+    true
+  } else {
+    false
+  }
+}
+
+fn hold_timeout(wm: &CascadeWindowManager) -> Duration {
+  Duration::from_millis(wm.config().key_hold_timeout.unwrap_or(DEFAULT_HOLD_TIMEOUT))
+}
+
+pub(crate) fn handle_key_event(wm: &CascadeWindowManager, event: &KeyboardEvent) -> bool {
+  let keycode = event.xkb_keycode();
+  if event.state() == KeyState::Pressed {
+    wm.keyboard_devices
+      .set_last_device(event.keyboard().device_name());
+    let binding = binding_for_event(wm, event);
+    // Shortcuts on the keysym take precedence over shortcuts on the physical key
+    let mut bindings = vec![binding.clone()];
+    if binding.shift || binding.mod5 {
+      let base_level_key = base_level_key(event);
+      if base_level_key != binding.key {
+        bindings.push(binding.with_key(base_level_key));
+      }
+    }
+    bindings.push(binding.with_key(Key::Code(keycode)));
+    wm.keyboard
+      .modifiers_held
+      .set(binding.has_held_modifiers() || binding.key.is_modifier());
+    let handled = handle_key_press(wm, event, &bindings);
+    // Without a held modifier there is no release to wait for, so a cycle
+    // started by the key ends right away
+    if !wm.keyboard.modifiers_held() {
+      finish_cycling_windows(wm);
+    }
+    handled
+  } else {
+    let binding = binding_for_event(wm, event);
+    wm.keyboard.modifiers_held.set(binding.has_held_modifiers());
+    if binding.key.is_modifier() && !binding.has_held_modifiers() {
+      finish_cycling_windows(wm);
+    }
+    handle_key_release(wm, keycode)
+  }
+}
+
+fn handle_key_press(
+  wm: &CascadeWindowManager,
+  event: &KeyboardEvent,
+  bindings: &[Keybinding],
+) -> bool {
+  // The held keys can no longer be tapped or held, so the client gets them
+  // before the key that interrupted them. They are passed on after the borrow
+  // ends, as that may emit new key events.
+  let replays = wm
+    .keyboard
+    .held_keys
+    .borrow_mut()
+    .iter_mut()
+    .filter_map(|held_key| {
+      held_key.interrupted = true;
+      held_key.take_replay()
+    })
+    .collect::<Vec<_>>();
+  for held_key in replays {
+    replay_press(wm, &held_key);
+  }
+
+  let release_binding = bindings
+    .iter()
+    .find(|binding| has_release_shortcuts(wm, binding))
+    .cloned();
+  let handled = handle_sequence(wm, bindings);
+  if let Some(binding) = release_binding {
+    // Modifiers are still passed on, so that they keep working together with
+    // other keys
+    let swallowed = handled || !bindings[0].key.is_modifier();
+    wm.keyboard.held_keys.borrow_mut().push(HeldKey {
+      keycode: event.xkb_keycode(),
+      time_msec: event.time_msec(),
+      binding,
+      pressed_at: Instant::now(),
+      interrupted: false,
+      held: false,
+      swallowed,
+      replay: swallowed && !handled,
+    });
+    set_hold_timer(wm, hold_timeout(wm));
+    swallowed
+  } else {
+    handled
+  }
+}
+
+fn handle_key_release(wm: &CascadeWindowManager, keycode: u32) -> bool {
+  let held_key = {
+    let mut held_keys = wm.keyboard.held_keys.borrow_mut();
+    held_keys
+      .iter()
+      .position(|held_key| held_key.keycode == keycode)
+      .map(|index| held_keys.remove(index))
+  };
+
+  if let Some(mut held_key) = held_key {
+    trace!("Released key {:?}", &held_key);
+    let tapped =
+      !held_key.interrupted && !held_key.held && held_key.pressed_at.elapsed() < hold_timeout(wm);
+    let mut triggered = false;
+    if tapped {
+      triggered |= trigger_binding(wm, held_key.binding.with_trigger(KeyTrigger::Tap));
+    }
+    triggered |= trigger_binding(wm, held_key.binding.with_trigger(KeyTrigger::Release));
+    if !triggered {
+      if let Some(replay) = held_key.take_replay() {
+        replay_press(wm, &replay);
+      }
     }
+    held_key.swallowed
   } else {
     false
   }
 }
+
+/// Passes a key press that was held back for tap and hold shortcuts on to the
+/// client, after all
+fn replay_press(wm: &CascadeWindowManager, held_key: &HeldKey) {
+  trace!("Passing on held back key {}", held_key.binding);
+  wm.input_manager
+    .notify_key(held_key.time_msec, held_key.keycode, KeyState::Pressed);
+}
+
+fn set_hold_timer(wm: &CascadeWindowManager, timeout: Duration) {
+  if let Some(ref hold_timer) = *wm.hold_timer.borrow() {
+    hold_timer.set_timeout(timeout);
+  }
+}
+
+/// Triggers hold shortcuts for keys that have been held long enough.
+///
+/// Called by the hold timer, which is set whenever a key with release, tap or
+/// hold shortcuts is pressed.
+pub(crate) fn handle_hold_timer(wm: &CascadeWindowManager) {
+  let timeout = hold_timeout(wm);
+  let held_keys = wm
+    .keyboard
+    .held_keys
+    .borrow_mut()
+    .iter_mut()
+    .filter(|held_key| {
+      !held_key.held && !held_key.interrupted && held_key.pressed_at.elapsed() >= timeout
+    })
+    .map(|held_key| {
+      held_key.held = true;
+      (held_key.keycode, held_key.binding.clone())
+    })
+    .collect::<Vec<_>>();
+  for (keycode, binding) in held_keys {
+    let triggered = trigger_binding(wm, binding.with_trigger(KeyTrigger::Hold));
+    let replay = wm
+      .keyboard
+      .held_keys
+      .borrow_mut()
+      .iter_mut()
+      .find(|held_key| held_key.keycode == keycode)
+      .and_then(|held_key| {
+        if triggered {
+          held_key.replay = false;
+          None
+        } else {
+          held_key.take_replay()
+        }
+      });
+    if let Some(replay) = replay {
+      replay_press(wm, &replay);
+    }
+  }
+
+  // The timer may fire slightly early
+  let remaining = wm
+    .keyboard
+    .held_keys
+    .borrow()
+    .iter()
+    .filter(|held_key| !held_key.held && !held_key.interrupted)
+    .map(|held_key| timeout - held_key.pressed_at.elapsed().min(timeout))
+    .min();
+  if let Some(remaining) = remaining {
+    set_hold_timer(wm, remaining.max(Duration::from_millis(1)));
+  }
+}
+
+/// Handles a key press as part of a key sequence.
+///
+/// The bindings are alternative ways to refer to the pressed key, where the
+/// first binding that matches is used.
+fn handle_sequence(wm: &CascadeWindowManager, bindings: &[Keybinding]) -> bool {
+  let mode = wm.keyboard.mode();
+
+  let timeout = Duration::from_millis(
+    wm.config()
+      .key_sequence_timeout
+      .unwrap_or(DEFAULT_SEQUENCE_TIMEOUT),
+  );
+  if let Some(pending_since) = wm.keyboard.pending_since.get() {
+    if pending_since.elapsed() > timeout {
+      trace!("Key sequence timed out");
+      wm.keyboard.reset_sequence();
+    }
+  }
+  let has_pending_sequence = !wm.keyboard.pending_sequence.borrow().is_empty();
+  if has_pending_sequence && bindings[0].key.is_modifier() {
+    // Modifiers are pressed on their own before the next key in the sequence
+    return false;
+  }
+  let pending_sequence = wm.keyboard.pending_sequence.borrow().clone();
+  let mut candidates = bindings
+    .iter()
+    .map(|binding| {
+      let mut sequence = pending_sequence.clone();
+      sequence.push(binding.clone());
+      let (shortcut, has_prefix) = active_shortcut(wm, &sequence);
+      (sequence, shortcut, has_prefix)
+    })
+    .collect::<Vec<_>>();
+  let index = candidates
+    .iter()
+    .position(|(_, shortcut, has_prefix)| shortcut.is_some() || *has_prefix)
+    .unwrap_or(0);
+  let (sequence, shortcut, has_prefix) = candidates.swap_remove(index);
+
+  trace!(
+    "Pressed key {}, mode: {:?}, sequence: {:?}",
+    bindings[0],
+    &mode,
+    &sequence
+  );
+  if let Some(shortcut) = shortcut {
+    debug!("Triggering shortcut");
+    wm.keyboard.reset_sequence();
+    shortcut.triggered(wm);
+    true
+  } else if has_prefix {
+    *wm.keyboard.pending_sequence.borrow_mut() = sequence;
+    wm.keyboard.pending_since.set(Some(Instant::now()));
+    true
+  } else if has_pending_sequence {
+    // Unknown keys cancel the sequence, but are not passed on to the
+    // client as they were meant for the compositor
+    trace!("Key sequence cancelled");
+    wm.keyboard.reset_sequence();
+    true
+  } else if mode.is_some() && sequence[0].key == Key::Sym(xkb::KEY_Escape) {
+    exit_mode(wm);
+    true
+  } else {
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(keys: &str) -> Result<KeySequence, serde_json::Error> {
+    serde_json::from_value(serde_json::Value::String(keys.to_string()))
+  }
+
+  fn serialize(sequence: &KeySequence) -> String {
+    match serde_json::to_value(sequence).unwrap() {
+      serde_json::Value::String(keys) => keys,
+      other => panic!("Expected a string, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn key_sequences_round_trip() {
+    let sequences = [
+      "super+Return",
+      "ctrl+shift+t",
+      "super+w, h",
+      "alt+Tab:release",
+      "Super_L:tap",
+      "super+space:hold",
+      "super+code:10",
+      "mod3+mod5+caps_lock+num_lock+space",
+    ];
+    for keys in sequences.iter() {
+      let sequence = parse(keys).unwrap();
+      assert_eq!(serialize(&sequence), *keys);
+      assert_eq!(parse(&serialize(&sequence)).unwrap(), sequence);
+    }
+  }
+
+  #[test]
+  fn key_sequences_are_normalized() {
+    let normalized = [
+      ("shift+logo+Return", "shift+super+Return"),
+      ("control+mod1+Delete", "alt+ctrl+Delete"),
+      ("hyper+altgr+a", "mod3+mod5+a"),
+      ("super + w h", "super+w, h"),
+      ("super+x:press", "super+x"),
+    ];
+    for (keys, expected) in normalized.iter() {
+      assert_eq!(serialize(&parse(keys).unwrap()), *expected);
+    }
+  }
+
+  #[test]
+  fn invalid_key_sequences_are_rejected() {
+    for keys in ["", "super+", "super+NotAKey", "meta+a", "super+code:x"].iter() {
+      assert!(parse(keys).is_err(), "{:?} should be rejected", keys);
+    }
+  }
+}
@@ -0,0 +1,179 @@
+use crate::{
+  config::Config, ipc::protocol::IpcEvent, window_manager::CascadeWindowManager,
+  window_rules::Pattern,
+};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeMap, error::Error};
+use wlral::input::keyboard::{Keyboard, KeyboardConfig};
+
+const DEFAULT_REPEAT_RATE: i32 = 25;
+const DEFAULT_REPEAT_DELAY: i32 = 600;
+
+/// Keyboard settings for the input devices with a matching name.
+///
+/// Devices without a matching config use keyboard_layouts.
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KeyboardDeviceConfig {
+  pub(crate) name: Option<Pattern>,
+  /// Layouts to cycle through, the first is used when the device is connected.
+  /// If empty, the layout from keyboard_layouts is used.
+  pub(crate) layouts: Vec<KeyboardConfig>,
+  /// Repeated keys per second
+  pub(crate) repeat_rate: Option<i32>,
+  /// Milliseconds before a held key starts repeating
+  pub(crate) repeat_delay: Option<i32>,
+}
+
+impl KeyboardDeviceConfig {
+  pub(crate) fn validate(config: &Config) -> Result<(), Box<dyn Error>> {
+    for (i, device) in config.keyboard_devices.iter().enumerate() {
+      if device.name.is_none() {
+        return Err(format!("keyboard_devices[{}].name: must be specified", i).into());
+      }
+      for (j, a) in device.layouts.iter().enumerate() {
+        if device.layouts.iter().skip(j + 1).any(|b| a == b) {
+          return Err(
+            format!(
+              "keyboard_devices[{}].layouts: Duplicated keyboard layout in index {}: {:?}",
+              i, j, a
+            )
+            .into(),
+          );
+        }
+      }
+      if device.repeat_rate.map_or(false, |rate| rate < 0) {
+        return Err(format!("keyboard_devices[{}].repeat_rate: must not be negative", i).into());
+      }
+      if device.repeat_delay.map_or(false, |delay| delay < 0) {
+        return Err(format!("keyboard_devices[{}].repeat_delay: must not be negative", i).into());
+      }
+    }
+
+    Ok(())
+  }
+
+  /// The first config that matches the device name
+  fn for_device<'a>(
+    devices: &'a [KeyboardDeviceConfig],
+    device_name: &str,
+  ) -> Option<&'a KeyboardDeviceConfig> {
+    devices.iter().find(|device| {
+      device
+        .name
+        .as_ref()
+        .map_or(false, |name| name.is_match(device_name))
+    })
+  }
+}
+
+/// Keeps track of the layout of each keyboard device with its own layouts.
+#[derive(Default, Debug)]
+pub(crate) struct KeyboardDevices {
+  /// Index in the device's layouts by device name
+  layout_indices: RefCell<BTreeMap<String, usize>>,
+  /// Name of the device that the last key was pressed on
+  last_device: RefCell<Option<String>>,
+}
+
+impl KeyboardDevices {
+  pub(crate) fn set_last_device(&self, device_name: String) {
+    *self.last_device.borrow_mut() = Some(device_name);
+  }
+
+  pub(crate) fn last_device(&self) -> Option<String> {
+    self.last_device.borrow().clone()
+  }
+
+  /// The layout of the device that the last key was pressed on
+  pub(crate) fn active_layout(&self, wm: &CascadeWindowManager) -> KeyboardConfig {
+    let config = wm.config();
+    let device_layout = self.last_device().and_then(|device_name| {
+      let device = KeyboardDeviceConfig::for_device(&config.keyboard_devices, &device_name)?;
+      let index = self
+        .layout_indices
+        .borrow()
+        .get(&device_name)
+        .cloned()
+        .filter(|index| *index < device.layouts.len())
+        .unwrap_or(0);
+      device.layouts.get(index).cloned()
+    });
+    device_layout.unwrap_or_else(|| wm.config_manager.config().keyboard.clone())
+  }
+}
+
+/// Applies the matching device config, if any, to the keyboard.
+///
+/// Must be called again after the global keyboard config changes, as that is
+/// applied to all keyboards.
+pub(crate) fn apply_device_config(wm: &CascadeWindowManager, keyboard: &Keyboard) {
+  let config = wm.config();
+  let device_name = keyboard.device_name();
+  let device = match KeyboardDeviceConfig::for_device(&config.keyboard_devices, &device_name) {
+    Some(device) => device,
+    None => return,
+  };
+
+  if !device.layouts.is_empty() {
+    let index = wm
+      .keyboard_devices
+      .layout_indices
+      .borrow()
+      .get(&device_name)
+      .cloned()
+      .filter(|index| *index < device.layouts.len())
+      .unwrap_or(0);
+    debug!(
+      "Setting keyboard layout of \"{}\" to: {:?}",
+      device_name, &device.layouts[index]
+    );
+    keyboard.set_keyboard_config(&device.layouts[index]);
+  }
+  if device.repeat_rate.is_some() || device.repeat_delay.is_some() {
+    keyboard.set_repeat_info(
+      device.repeat_rate.unwrap_or(DEFAULT_REPEAT_RATE),
+      device.repeat_delay.unwrap_or(DEFAULT_REPEAT_DELAY),
+    );
+  }
+}
+
+pub(crate) fn apply_all_device_configs(wm: &CascadeWindowManager) {
+  for keyboard in wm.input_manager.keyboards().iter() {
+    apply_device_config(wm, keyboard);
+  }
+}
+
+/// Switches to the next layout of the device's own layouts.
+///
+/// Returns false if the device has no layouts of its own.
+pub(crate) fn switch_device_keyboard_layout(wm: &CascadeWindowManager, device_name: &str) -> bool {
+  let next_layout = {
+    let config = wm.config();
+    let device = KeyboardDeviceConfig::for_device(&config.keyboard_devices, device_name);
+    let layouts = match device {
+      Some(device) if !device.layouts.is_empty() => &device.layouts,
+      _ => return false,
+    };
+    let mut layout_indices = wm.keyboard_devices.layout_indices.borrow_mut();
+    let index = layout_indices.entry(device_name.to_string()).or_insert(0);
+    *index = (*index + 1) % layouts.len();
+    layouts[*index].clone()
+  };
+
+  debug!(
+    "Switching keyboard layout of \"{}\" to: {:?}",
+    device_name, &next_layout
+  );
+  for keyboard in wm.input_manager.keyboards().iter() {
+    if keyboard.device_name() == device_name {
+      keyboard.set_keyboard_config(&next_layout);
+    }
+  }
+  wm.broadcast_ipc_event(|| IpcEvent::KeyboardLayoutSwitched {
+    layout: next_layout,
+    device: Some(device_name.to_string()),
+  });
+  true
+}
@@ -0,0 +1,111 @@
+use crate::window_manager::CascadeWindowManager;
+use log::trace;
+use std::{cell::RefCell, rc::Rc};
+use wlral::{
+  geometry::Point,
+  window::{KeyboardInteractivity, Window, WindowLayer},
+};
+
+/// Keeps track of mapped layer shell surfaces, like panels, docks, launchers
+/// and notifications.
+///
+/// Layer surfaces are placed by the compositor according to their anchors and
+/// are never part of a workspace. Their exclusive zones are excluded from the
+/// usable area of the output.
+#[derive(Debug, Default)]
+pub(crate) struct LayerSurfaces {
+  surfaces: RefCell<Vec<Rc<Window>>>,
+}
+
+impl LayerSurfaces {
+  /// The most recently mapped surface that requires exclusive keyboard focus
+  pub(crate) fn exclusive_focus(&self) -> Option<Rc<Window>> {
+    self
+      .surfaces
+      .borrow()
+      .iter()
+      .rev()
+      .find(|surface| has_exclusive_focus(surface))
+      .cloned()
+  }
+}
+
+pub(crate) fn is_layer_surface(window: &Window) -> bool {
+  window.layer() != WindowLayer::Normal
+}
+
+/// Surfaces in the top and overlay layers can take the keyboard from all
+/// other windows, for example lock screens and launchers
+fn has_exclusive_focus(window: &Window) -> bool {
+  let layer = window.layer();
+  (layer == WindowLayer::Top || layer == WindowLayer::Overlay)
+    && window.keyboard_interactivity() == KeyboardInteractivity::Exclusive
+}
+
+pub(crate) fn handle_layer_surface_ready(wm: &CascadeWindowManager, window: Rc<Window>) {
+  trace!(
+    "Layer surface ready in {:?}: {:?}",
+    window.layer(),
+    window.title()
+  );
+  wm.layer_surfaces.surfaces.borrow_mut().push(window.clone());
+  // Surfaces that want exclusive keyboard focus, such as lock screens and
+  // launchers, get it as soon as they are mapped. Surfaces that only take the
+  // keyboard on demand are focused when clicked instead.
+  if has_exclusive_focus(&window) && window.can_receive_focus() {
+    wm.window_manager.focus_window(window);
+  }
+}
+
+/// Focuses the topmost surface under the pointer that takes the keyboard on
+/// demand
+pub(crate) fn focus_clicked_surface(wm: &CascadeWindowManager, position: Point) {
+  let surface = wm
+    .layer_surfaces
+    .surfaces
+    .borrow()
+    .iter()
+    .rev()
+    .find(|surface| {
+      surface.keyboard_interactivity() == KeyboardInteractivity::OnDemand
+        && surface.extents().contains(&position)
+    })
+    .cloned();
+  if let Some(surface) = surface {
+    if surface.can_receive_focus() && !wm.window_manager.window_has_focus(&surface) {
+      wm.window_manager.focus_window(surface);
+    }
+  }
+}
+
+/// Returns false if the focus was taken back by a surface with exclusive
+/// keyboard focus
+pub(crate) fn advise_focused_window(wm: &CascadeWindowManager, window: &Rc<Window>) -> bool {
+  match wm.layer_surfaces.exclusive_focus() {
+    Some(surface) if surface != *window => {
+      wm.window_manager.focus_window(surface);
+      false
+    }
+    _ => true,
+  }
+}
+
+pub(crate) fn advise_delete_layer_surface(wm: &CascadeWindowManager, window: Rc<Window>) {
+  trace!("Layer surface deleted: {:?}", window.title());
+  wm.layer_surfaces
+    .surfaces
+    .borrow_mut()
+    .retain(|surface| *surface != window);
+
+  // Give the keyboard back to the window that had it before the surface
+  if wm.window_manager.window_has_focus(&window) || has_exclusive_focus(&window) {
+    let next_window = wm
+      .layer_surfaces
+      .exclusive_focus()
+      .or_else(|| wm.active_window());
+    match next_window {
+      Some(next_window) => wm.window_manager.focus_window(next_window),
+      None => wm.window_manager.blur(),
+    }
+  }
+}
@@ -3,12 +3,18 @@ mod animation;
 mod background;
 mod config;
 mod entities;
+mod gaps;
+mod ipc;
 mod keyboard;
+mod keyboard_devices;
+mod layer_shell;
 mod pointer;
+mod session;
+mod shortcuts;
 mod window_manager;
+mod window_rules;
 
-use crate::window_manager::CascadeWindowManager;
-use background::BackgroundConfig;
+use crate::window_manager::{CascadeWindowManager, SharedWindowManager};
 use config::Config;
 use log::error;
 use wlral::compositor::Compositor;
@@ -29,8 +35,7 @@ fn main() {
   });
 
   let window_manager = CascadeWindowManager::init(config, &compositor);
-  BackgroundConfig::init(&window_manager.config, compositor.config_manager());
-  compositor
-    .run(window_manager)
-    .expect("Could not start compositor");
+  let result = compositor.run(SharedWindowManager(window_manager.clone()));
+  window_manager.session.flush(&window_manager);
+  result.expect("Could not start compositor");
 }
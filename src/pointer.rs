@@ -1,5 +1,6 @@
 use crate::actions::*;
 use crate::entities::*;
+use crate::layer_shell::{self, is_layer_surface};
 use crate::window_manager::CascadeWindowManager;
 use wlral::geometry::*;
 use wlral::input::events::*;
@@ -23,13 +24,45 @@ pub(crate) fn handle_motion_event(wm: &CascadeWindowManager, event: &MotionEvent
       .get(output)
       .cloned()
       .expect("Output should be assigned a workspace");
-    wm.focus_workspace(&workspace);
+    // Layer surfaces keep the keyboard while the pointer moves, unless it
+    // moves to another workspace and they did not ask for it exclusively
+    let layer_surface_focused = wm
+      .window_manager
+      .focused_window()
+      .map_or(false, |window| is_layer_surface(&window));
+    let keep_focus = wm.layer_surfaces.exclusive_focus().is_some()
+      || (layer_surface_focused && wm.mru_workspaces().top() == Some(&workspace));
+    if !keep_focus {
+      wm.focus_workspace(&workspace);
+    }
   }
 
   match *wm.gesture.borrow() {
     Gesture::Move(ref gesture) => {
       let window = &gesture.window;
-      if new_cursor.y < 100 {
+      let floating = wm
+        .workspace_by_window(window)
+        .map_or(false, |workspace| workspace.is_floating(window));
+      if floating {
+        if let Some(ref over_output) = over_output {
+          let output_workspace = wm
+            .output_workspaces
+            .borrow()
+            .get(over_output)
+            .cloned()
+            .expect("Output should be assigned a workspace");
+          if !output_workspace.has_window(window) {
+            move_specified_window_to_workspace(
+              wm,
+              gesture.window.clone(),
+              &output_workspace,
+              WorkspacePosition::Coordinate(new_cursor),
+            );
+          }
+        }
+
+        window.move_to((event.position() - gesture.drag_point.as_displacement()).into());
+      } else if new_cursor.y < 100 {
         if let Some(workspace) = wm.workspace_by_window(&gesture.window) {
           let pre_scroll_left = workspace.scroll_left();
           workspace.set_scroll_left(pre_scroll_left - event.delta().dx as i32);
@@ -44,6 +77,7 @@ pub(crate) fn handle_motion_event(wm: &CascadeWindowManager, event: &MotionEvent
         }
       } else {
         let window_width = window.size().width();
+        let inner_gap = wm.config().gaps.inner;
         if let Some(workspace) = wm.workspace_by_window(&gesture.window) {
           if let Some(ref over_output) = over_output {
             let output_workspace = wm
@@ -65,7 +99,9 @@ pub(crate) fn handle_motion_event(wm: &CascadeWindowManager, event: &MotionEvent
 
           if let Some(left_window) = workspace.window_by_direction(&window, Direction::Left) {
             if new_cursor.x
-              < left_window.extents().left() + left_window.size().width() / 2 + window_width / 2
+              < left_window.extents().left()
+                + left_window.size().width() / 2
+                + (window_width + inner_gap) / 2
             {
               let _ = workspace.move_window(&window, Direction::Left);
               arrange_windows_workspace(wm, workspace);
@@ -74,7 +110,8 @@ pub(crate) fn handle_motion_event(wm: &CascadeWindowManager, event: &MotionEvent
             workspace.window_by_direction(&window, Direction::Right)
           {
             if new_cursor.x
-              > right_window.extents().left() + right_window.size().width() / 2 - window_width / 2
+              > right_window.extents().left() + right_window.size().width() / 2
+                - (window_width + inner_gap) / 2
             {
               let _ = workspace.move_window(&window, Direction::Right);
               arrange_windows_workspace(wm, workspace);
@@ -102,7 +139,10 @@ pub(crate) fn handle_motion_event(wm: &CascadeWindowManager, event: &MotionEvent
         extents.top_left.x += displacement.dx;
         extents.size.width -= displacement.dx;
 
-        if let Some(workspace) = wm.workspace_by_window(&gesture.window) {
+        let tiled_workspace = wm
+          .workspace_by_window(&gesture.window)
+          .filter(|workspace| !workspace.is_floating(&gesture.window));
+        if let Some(workspace) = tiled_workspace {
           let current_extents = gesture.window.extents();
           let delta_x = current_extents.left() - extents.left();
           if delta_x < 0 {
@@ -129,6 +169,9 @@ pub(crate) fn handle_motion_event(wm: &CascadeWindowManager, event: &MotionEvent
 }
 
 pub(crate) fn handle_button_event(wm: &CascadeWindowManager, event: &ButtonEvent) -> bool {
+  if event.state() == ButtonState::Pressed {
+    layer_shell::focus_clicked_surface(wm, event.position().into());
+  }
   if event.state() == ButtonState::Released {
     let gesture_window = wm.gesture.borrow().window();
     if let Some(window) = gesture_window {
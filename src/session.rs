@@ -0,0 +1,384 @@
+use crate::{
+  actions::{center_floating_window, target_column_width},
+  entities::{workspace::WorkspacePosition, Workspace},
+  window_manager::CascadeWindowManager,
+  window_rules::{Pattern, WindowRule},
+};
+use log::{debug, error, trace};
+use serde::{Deserialize, Serialize};
+use std::{
+  cell::{Cell, RefCell},
+  env,
+  error::Error,
+  fs,
+  path::PathBuf,
+  rc::{Rc, Weak},
+  time::{Duration, Instant},
+};
+use wlral::window::Window;
+
+/// How often the session file may be written while the layout changes
+const SAVE_INTERVAL: Duration = Duration::from_secs(2);
+/// How long after startup windows from the previous session are waited for
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SessionState {
+  pub(crate) workspaces: Vec<WorkspaceState>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WorkspaceState {
+  pub(crate) index: usize,
+  pub(crate) name: Option<String>,
+  pub(crate) output: Option<String>,
+  pub(crate) scroll_left: i32,
+  pub(crate) columns: Vec<ColumnState>,
+  pub(crate) floating_windows: Vec<WindowState>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ColumnState {
+  pub(crate) width: i32,
+  pub(crate) windows: Vec<WindowState>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WindowState {
+  pub(crate) app_id: Option<String>,
+  pub(crate) title: Option<String>,
+}
+
+impl SessionState {
+  pub(crate) fn path() -> PathBuf {
+    let state_dir = env::var("XDG_STATE_HOME")
+      .unwrap_or_else(|_| shellexpand::tilde("~/.local/state").to_string());
+    PathBuf::from(state_dir)
+      .join("cascade")
+      .join("session.json")
+  }
+
+  pub(crate) fn load() -> Result<SessionState, Box<dyn Error>> {
+    let state_string = fs::read_to_string(SessionState::path())?;
+    Ok(serde_json::from_str(&state_string)?)
+  }
+
+  pub(crate) fn save(&self) -> Result<(), Box<dyn Error>> {
+    let path = SessionState::path();
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    // Write to a temporary file first so that a crash never leaves a
+    // truncated session behind
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+  }
+
+  pub(crate) fn capture(wm: &CascadeWindowManager) -> SessionState {
+    fn window_state(window: &Rc<Window>) -> WindowState {
+      WindowState {
+        app_id: window.app_id(),
+        title: window.title(),
+      }
+    }
+
+    let mut workspaces = wm
+      .mru_workspaces()
+      .iter()
+      .map(|workspace| WorkspaceState {
+        index: workspace.index(),
+        name: wm
+          .config()
+          .workspace_name(workspace.index())
+          .map(str::to_string),
+        output: wm
+          .output_by_workspace(workspace)
+          .map(|output| output.name().to_string()),
+        scroll_left: workspace.scroll_left(),
+        columns: workspace
+          .columns()
+          .iter()
+          .map(|column| ColumnState {
+            width: target_column_width(wm, workspace, column),
+            windows: column.windows().iter().map(window_state).collect(),
+          })
+          .collect(),
+        floating_windows: workspace
+          .floating_windows()
+          .iter()
+          .map(window_state)
+          .collect(),
+      })
+      .collect::<Vec<_>>();
+    workspaces.sort_by_key(|workspace| workspace.index);
+
+    SessionState { workspaces }
+  }
+}
+
+/// Where a window from the previous session should be placed
+#[derive(Debug)]
+struct PendingWindow {
+  rule: WindowRule,
+  workspace: usize,
+  window_width: i32,
+  column: usize,
+  row: usize,
+}
+
+fn set_session_timer(wm: &CascadeWindowManager, timeout: Duration) {
+  if let Some(ref session_timer) = *wm.session_timer.borrow() {
+    session_timer.set_timeout(timeout);
+  }
+}
+
+/// Saves the layout as it changes, and puts windows back where they were
+/// when they reappear after a restart.
+///
+/// Nothing is saved until all windows have been restored or
+/// [`RESTORE_TIMEOUT`] has passed, so that a partially restored layout does
+/// not overwrite the previous session.
+pub(crate) struct Session {
+  previous_state: SessionState,
+  started: Instant,
+  pending_windows: RefCell<Vec<PendingWindow>>,
+  /// Restored windows with the workspace index and column they were restored to
+  restored_windows: RefCell<Vec<(Weak<Window>, usize, usize)>>,
+  dirty: Cell<bool>,
+  last_save: Cell<Option<Instant>>,
+}
+
+impl Session {
+  pub(crate) fn init() -> Session {
+    let previous_state = match SessionState::load() {
+      Ok(state) => state,
+      Err(error) => {
+        debug!("No previous session restored: {}", error);
+        SessionState::default()
+      }
+    };
+
+    let mut pending_windows = vec![];
+    for workspace in previous_state.workspaces.iter() {
+      for (column_index, column) in workspace.columns.iter().enumerate() {
+        for (row_index, window) in column.windows.iter().enumerate() {
+          if let Some(rule) = Session::window_rule(window, false) {
+            pending_windows.push(PendingWindow {
+              rule,
+              workspace: workspace.index,
+              window_width: column.width,
+              column: column_index,
+              row: row_index,
+            });
+          }
+        }
+      }
+      for window in workspace.floating_windows.iter() {
+        if let Some(rule) = Session::window_rule(window, true) {
+          pending_windows.push(PendingWindow {
+            rule,
+            workspace: workspace.index,
+            window_width: 0,
+            column: 0,
+            row: 0,
+          });
+        }
+      }
+    }
+
+    Session {
+      previous_state,
+      started: Instant::now(),
+      pending_windows: RefCell::new(pending_windows),
+      restored_windows: RefCell::new(vec![]),
+      dirty: Cell::new(false),
+      last_save: Cell::new(None),
+    }
+  }
+
+  fn window_rule(window: &WindowState, floating: bool) -> Option<WindowRule> {
+    // Without an app_id there is no reliable way to recognize the window
+    let app_id = window.app_id.as_ref()?;
+    Some(WindowRule {
+      app_id: Some(Pattern::exact(app_id)),
+      title: window.title.as_ref().map(|title| Pattern::exact(title)),
+      floating: Some(floating),
+      ..WindowRule::default()
+    })
+  }
+
+  /// The workspace that was shown on the output in the previous session
+  pub(crate) fn workspace_for_output(&self, output_name: &str) -> Option<&WorkspaceState> {
+    self
+      .previous_state
+      .workspaces
+      .iter()
+      .find(|workspace| workspace.output.as_deref() == Some(output_name))
+  }
+
+  /// True while there are windows from the previous session left to restore
+  fn is_restoring(&self) -> bool {
+    let mut pending_windows = self.pending_windows.borrow_mut();
+    if pending_windows.is_empty() {
+      return false;
+    }
+    if self.started.elapsed() < RESTORE_TIMEOUT {
+      return true;
+    }
+    debug!(
+      "Giving up on restoring {} windows from the previous session",
+      pending_windows.len()
+    );
+    pending_windows.clear();
+    self.restored_windows.borrow_mut().clear();
+    false
+  }
+
+  pub(crate) fn mark_dirty(&self) {
+    self.dirty.set(true);
+  }
+
+  /// Saves the session if it has changed, at most once per SAVE_INTERVAL.
+  ///
+  /// If the save has to wait, the session timer is set to try again, so that
+  /// the last change is saved even if nothing else happens.
+  pub(crate) fn save_if_dirty(&self, wm: &CascadeWindowManager) {
+    if !self.dirty.get() {
+      return;
+    }
+    if self.is_restoring() {
+      set_session_timer(wm, SAVE_INTERVAL);
+      return;
+    }
+    if let Some(last_save) = self.last_save.get() {
+      let elapsed = last_save.elapsed();
+      if elapsed < SAVE_INTERVAL {
+        set_session_timer(wm, SAVE_INTERVAL - elapsed);
+        return;
+      }
+    }
+    self.write(wm);
+  }
+
+  /// Saves the session right away if it has changed, e.g. when exiting
+  pub(crate) fn flush(&self, wm: &CascadeWindowManager) {
+    if self.dirty.get() && !self.is_restoring() {
+      self.write(wm);
+    }
+  }
+
+  fn write(&self, wm: &CascadeWindowManager) {
+    self.dirty.set(false);
+    self.last_save.set(Some(Instant::now()));
+
+    trace!("Saving session to {:?}", SessionState::path());
+    if let Err(error) = SessionState::capture(wm).save() {
+      error!("Could not save session: {}", error);
+    }
+  }
+
+  /// Places the window where a matching window was in the previous session.
+  ///
+  /// Windows are preferably matched on both app_id and title, falling back to
+  /// only app_id as titles often change. The floating state and width set by
+  /// the window rule take precedence over the previous session. Returns false
+  /// if there was no match.
+  pub(crate) fn restore_window(
+    &self,
+    wm: &CascadeWindowManager,
+    window: &Rc<Window>,
+    rule: &WindowRule,
+  ) -> bool {
+    if !self.is_restoring() {
+      return false;
+    }
+    let pending = {
+      let mut pending_windows = self.pending_windows.borrow_mut();
+      let index = pending_windows
+        .iter()
+        .position(|pending| pending.rule.matches(window))
+        .or_else(|| {
+          pending_windows.iter().position(|pending| {
+            WindowRule {
+              title: None,
+              ..pending.rule.clone()
+            }
+            .matches(window)
+          })
+        });
+      match index {
+        Some(index) => pending_windows.remove(index),
+        None => return false,
+      }
+    };
+    let workspace_index = pending.workspace;
+    let workspace = wm.workspace_by_index(workspace_index);
+    debug!(
+      "Restoring window \"{:?}\" to workspace {}, column {}",
+      window.title(),
+      workspace_index,
+      pending.column
+    );
+
+    if rule.floating.or(pending.rule.floating) == Some(true) {
+      workspace.add_floating_window(window.clone());
+      if let Some(output) = wm.output_by_workspace(&workspace) {
+        center_floating_window(wm, window.clone(), &output);
+      }
+      return true;
+    }
+
+    let width = wm
+      .rule_width(rule, &workspace)
+      .unwrap_or(pending.window_width);
+    if width > 0 {
+      window.resize(window.size().with_width(width));
+    }
+
+    let mut restored_windows = self.restored_windows.borrow_mut();
+    restored_windows.retain(|(restored_window, _, _)| restored_window.upgrade().is_some());
+    let column_window = restored_windows
+      .iter()
+      .filter(|(_, w, c)| *w == workspace_index && *c == pending.column)
+      .filter_map(|(restored_window, _, _)| restored_window.upgrade())
+      .find(|restored_window| workspace.has_window(restored_window));
+
+    if let Some(column_window) = column_window {
+      workspace.add_window_to_column(window.clone(), &column_window, pending.row);
+    } else {
+      let column_index = Session::restored_column_index(&restored_windows, &workspace, &pending);
+      workspace.add_window(window.clone(), WorkspacePosition::Column(column_index));
+    }
+    restored_windows.push((Rc::downgrade(window), workspace_index, pending.column));
+    true
+  }
+
+  /// Counts the restored columns that should be to the left of the pending
+  /// window, to insert it in the same order as in the previous session
+  fn restored_column_index(
+    restored_windows: &[(Weak<Window>, usize, usize)],
+    workspace: &Workspace,
+    pending: &PendingWindow,
+  ) -> usize {
+    let mut columns = restored_windows
+      .iter()
+      .filter(|(restored_window, w, c)| {
+        *w == workspace.index()
+          && *c < pending.column
+          && restored_window.upgrade().map_or(false, |restored_window| {
+            workspace.has_window(&restored_window) && !workspace.is_floating(&restored_window)
+          })
+      })
+      .map(|(_, _, c)| *c)
+      .collect::<Vec<_>>();
+    columns.sort();
+    columns.dedup();
+    columns.len()
+  }
+}
@@ -0,0 +1,227 @@
+//! Actions that can be bound to keyboard shortcuts or sent over IPC.
+//!
+//! This module is shared with cascadectl, so it must not depend on anything
+//! else in Cascade.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "action")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ActionShortcut {
+  NavigateToFirst,
+  NavigateToLast,
+  Navigate {
+    direction: Direction,
+  },
+  NavigateWorkspace {
+    direction: VerticalDirection,
+  },
+  NavigateMonitor {
+    direction: Direction,
+  },
+  FocusWorkspace {
+    #[serde(flatten, with = "WorkspaceSelectorFields")]
+    workspace: WorkspaceSelector,
+  },
+  /// Walks through windows in most recently used order, until the modifiers
+  /// are released
+  CycleWindows {
+    #[serde(default)]
+    scope: CycleScope,
+    #[serde(default)]
+    direction: CycleDirection,
+  },
+
+  MoveWindow {
+    direction: Direction,
+  },
+  MoveWindowWorkspace {
+    direction: VerticalDirection,
+  },
+  MoveWindowMonitor {
+    direction: Direction,
+  },
+  MoveWindowToWorkspace {
+    #[serde(flatten, with = "WorkspaceSelectorFields")]
+    workspace: WorkspaceSelector,
+  },
+  PullWindow {
+    direction: Direction,
+  },
+  ExpelWindow,
+
+  ResizeWindow {
+    steps: Vec<f32>,
+  },
+  CenterWindow,
+  CloseWindow,
+  ToggleFloating,
+  ToggleFullscreen,
+  /// Hides the active window in the scratchpad, optionally under a name
+  MoveToScratchpad {
+    #[serde(default)]
+    name: Option<String>,
+  },
+  /// Shows a scratchpad window on the active workspace, or hides it if it is
+  /// already shown there
+  ToggleScratchpad {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    app_id: Option<String>,
+  },
+
+  SwitchKeyboardLayout {
+    #[serde(default)]
+    scope: LayoutScope,
+  },
+  /// Switches to the keybindings of a mode in keyboard_modes
+  EnterMode {
+    name: String,
+  },
+  /// Switches back to the keyboard_shortcuts keybindings
+  ExitMode,
+  ReloadConfig,
+
+  DebugPrintWindows,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum Direction {
+  #[serde(alias = "left")]
+  Left,
+  #[serde(alias = "right")]
+  Right,
+  #[serde(alias = "up")]
+  Up,
+  #[serde(alias = "down")]
+  Down,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum VerticalDirection {
+  #[serde(alias = "up")]
+  Up,
+  #[serde(alias = "down")]
+  Down,
+}
+
+/// Refers to a workspace either by its index or by its configured name
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum WorkspaceSelector {
+  Index(usize),
+  Name(String),
+}
+
+/// Selects a workspace in actions with either an index or a name field, like
+/// `{ action: focus_workspace, index: 2 }`
+#[derive(Serialize, Deserialize)]
+#[allow(dead_code)]
+#[serde(remote = "WorkspaceSelector")]
+#[serde(rename_all = "snake_case")]
+enum WorkspaceSelectorFields {
+  Index(usize),
+  Name(String),
+}
+
+/// Which keyboards SwitchKeyboardLayout switches the layout of
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LayoutScope {
+  /// Keyboards that use keyboard_layouts
+  #[default]
+  All,
+  /// The keyboard that triggered the action, if it has layouts of its own
+  Device,
+}
+
+/// Which windows CycleWindows walks through
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CycleScope {
+  /// Windows on the active workspace
+  Workspace,
+  /// Windows that are not shown on another output
+  Output,
+  #[default]
+  All,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CycleDirection {
+  /// Towards less recently used windows
+  #[default]
+  Next,
+  Previous,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::{json, Value};
+
+  fn parse(action: Value) -> Result<ActionShortcut, serde_json::Error> {
+    serde_json::from_value(action)
+  }
+
+  #[test]
+  fn workspace_selectors_are_index_or_name_fields() {
+    let by_index = ActionShortcut::FocusWorkspace {
+      workspace: WorkspaceSelector::Index(2),
+    };
+    let by_name = ActionShortcut::FocusWorkspace {
+      workspace: WorkspaceSelector::Name("web".to_string()),
+    };
+    assert_eq!(
+      parse(json!({ "action": "focus_workspace", "index": 2 })).unwrap(),
+      by_index
+    );
+    assert_eq!(
+      parse(json!({ "action": "focus_workspace", "name": "web" })).unwrap(),
+      by_name
+    );
+    assert_eq!(
+      serde_json::to_value(&by_index).unwrap(),
+      json!({ "action": "focus_workspace", "index": 2 })
+    );
+    assert_eq!(
+      serde_json::to_value(&by_name).unwrap(),
+      json!({ "action": "focus_workspace", "name": "web" })
+    );
+  }
+
+  #[test]
+  fn workspace_selectors_are_required() {
+    assert!(parse(json!({ "action": "focus_workspace" })).is_err());
+  }
+
+  #[test]
+  fn optional_arguments_have_defaults() {
+    assert_eq!(
+      parse(json!({ "action": "cycle_windows" })).unwrap(),
+      ActionShortcut::CycleWindows {
+        scope: CycleScope::All,
+        direction: CycleDirection::Next,
+      }
+    );
+    assert_eq!(
+      parse(json!({ "action": "switch_keyboard_layout" })).unwrap(),
+      ActionShortcut::SwitchKeyboardLayout {
+        scope: LayoutScope::All,
+      }
+    );
+  }
+
+  #[test]
+  fn directions_accept_lowercase() {
+    assert_eq!(
+      parse(json!({ "action": "navigate", "direction": "left" })).unwrap(),
+      ActionShortcut::Navigate {
+        direction: Direction::Left,
+      }
+    );
+  }
+}
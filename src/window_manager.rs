@@ -1,39 +1,58 @@
 use crate::config::Config;
 use crate::{
-  actions::{arrange_windows_all_workspaces, arrange_windows_workspace, resize_window},
+  actions::{
+    arrange_windows_all_workspaces, arrange_windows_workspace, center_floating_window,
+    resize_window, set_window_fullscreen, show_scratchpad_window,
+  },
   animation::AnimationManager,
+  background::BackgroundConfig,
   entities::{
     workspace::{Workspace, WorkspacePosition},
-    Gesture, MruList,
+    workspace_slide::WorkspaceAnimations,
+    Gesture, MruList, Scratchpad, WindowCycle,
   },
-  keyboard::handle_key_press,
+  ipc::{self, protocol::IpcEvent, IpcServer},
+  keyboard::{self, exit_mode, handle_key_event, KeyboardState},
+  keyboard_devices::{apply_all_device_configs, apply_device_config, KeyboardDevices},
+  layer_shell::{self, is_layer_surface, LayerSurfaces},
   pointer,
+  session::Session,
+  shortcuts::{VerticalDirection, WorkspaceSelector},
+  window_rules::{RulePosition, WindowRule},
 };
-use log::warn;
+use log::{error, info, warn};
 use std::{
   cell::{Ref, RefCell},
   cmp,
   collections::BTreeMap,
+  process::Child,
   rc::Rc,
 };
 use wlral::{
   compositor::Compositor,
   config::ConfigManager,
+  event_loop::EventSource,
+  geometry::Rectangle,
   input::{
     event_filter::EventFilter,
     events::{ButtonEvent, KeyboardEvent, MotionEvent},
+    input_manager::InputManager,
   },
+  listener,
   output::Output,
   output_manager::OutputManager,
   window::Window,
-  window_management_policy::{MaximizeRequest, MoveRequest, ResizeRequest, WindowManagementPolicy},
+  window_management_policy::{
+    FullscreenRequest, MaximizeRequest, MoveRequest, ResizeRequest, WindowManagementPolicy,
+  },
   window_manager::WindowManager,
 };
 
 pub(crate) struct CascadeWindowManager {
-  pub(crate) config: Config,
+  config: RefCell<Config>,
   pub(crate) config_manager: Rc<ConfigManager>,
   pub(crate) output_manager: Rc<OutputManager>,
+  pub(crate) input_manager: Rc<InputManager>,
   pub(crate) window_manager: Rc<WindowManager>,
   pub(crate) animation_manager: Rc<AnimationManager>,
   mru_windows: RefCell<MruList<Rc<Window>>>,
@@ -41,15 +60,41 @@ pub(crate) struct CascadeWindowManager {
   pub(crate) output_workspaces: RefCell<BTreeMap<Rc<Output>, Rc<Workspace>>>,
 
   pub(crate) gesture: RefCell<Gesture>,
+  pub(crate) window_cycle: RefCell<Option<WindowCycle>>,
+  /// Windows that asked for fullscreen before they were ready
+  pending_fullscreen: RefCell<Vec<Rc<Window>>>,
+  pub(crate) scratchpad: Scratchpad,
+  pub(crate) layer_surfaces: LayerSurfaces,
+  pub(crate) keyboard: KeyboardState,
+  pub(crate) keyboard_devices: KeyboardDevices,
+  swaybg: RefCell<Option<Child>>,
+  ipc_server: Option<IpcServer>,
+  ipc_source: RefCell<Option<EventSource>>,
+  /// Fires when a held key has been held long enough to trigger its hold shortcut
+  pub(crate) hold_timer: RefCell<Option<EventSource>>,
+  pub(crate) session: Session,
+  /// Fires when a session save that had to wait for the save interval is due
+  pub(crate) session_timer: RefCell<Option<EventSource>>,
 }
 
 impl CascadeWindowManager {
-  pub(crate) fn init(config: Config, compositor: &Compositor) -> CascadeWindowManager {
-    let animation_manager = AnimationManager::init(compositor.output_manager());
-    CascadeWindowManager {
-      config,
+  pub(crate) fn init(config: Config, compositor: &Compositor) -> Rc<CascadeWindowManager> {
+    let animation_manager =
+      AnimationManager::init(compositor.output_manager(), config.animations.clone());
+    // Started first so that the socket is exported to all processes started by Cascade
+    let ipc_server = match IpcServer::init() {
+      Ok(ipc_server) => Some(ipc_server),
+      Err(error) => {
+        error!("Could not start IPC server: {}", error);
+        None
+      }
+    };
+    let swaybg = BackgroundConfig::init(&config, compositor.config_manager());
+    let window_manager = Rc::new(CascadeWindowManager {
+      config: RefCell::new(config),
       config_manager: compositor.config_manager(),
       output_manager: compositor.output_manager(),
+      input_manager: compositor.input_manager(),
       window_manager: compositor.window_manager(),
       animation_manager,
       mru_windows: RefCell::new(MruList::new()),
@@ -57,39 +102,331 @@ impl CascadeWindowManager {
       output_workspaces: RefCell::new(BTreeMap::new()),
 
       gesture: RefCell::new(Gesture::None),
+      window_cycle: RefCell::new(None),
+      pending_fullscreen: RefCell::new(vec![]),
+      scratchpad: Scratchpad::default(),
+      layer_surfaces: LayerSurfaces::default(),
+      keyboard: KeyboardState::default(),
+      keyboard_devices: KeyboardDevices::default(),
+      swaybg: RefCell::new(swaybg),
+      ipc_server,
+      ipc_source: RefCell::new(None),
+      hold_timer: RefCell::new(None),
+      session: Session::init(),
+      session_timer: RefCell::new(None),
+    });
+    if let Some(ref ipc_server) = window_manager.ipc_server {
+      let ipc_source = compositor.event_loop().add_fd(
+        ipc_server.wake_fd(),
+        listener!(window_manager => move || {
+          window_manager.dispatch_ipc();
+        }),
+      );
+      *window_manager.ipc_source.borrow_mut() = Some(ipc_source);
+    }
+    let hold_timer = compositor
+      .event_loop()
+      .add_timer(listener!(window_manager => move || {
+        keyboard::handle_hold_timer(&window_manager);
+      }));
+    *window_manager.hold_timer.borrow_mut() = Some(hold_timer);
+    let session_timer = compositor
+      .event_loop()
+      .add_timer(listener!(window_manager => move || {
+        window_manager.session.save_if_dirty(&window_manager);
+      }));
+    *window_manager.session_timer.borrow_mut() = Some(session_timer);
+    compositor.output_manager().on_new_output().subscribe(
+      listener!(window_manager => move |output| {
+        output.on_frame().subscribe(listener!(window_manager => move || {
+          window_manager.handle_frame();
+        }));
+      }),
+    );
+    compositor.input_manager().on_new_keyboard().subscribe(
+      listener!(window_manager => move |keyboard| {
+        apply_device_config(&window_manager, &keyboard);
+      }),
+    );
+    window_manager
+  }
+
+  pub(crate) fn config(&self) -> Ref<Config> {
+    self.config.borrow()
+  }
+
+  /// Loads the config file again and applies it to the running compositor.
+  ///
+  /// If the new config fails to load or validate, the current config is kept.
+  pub(crate) fn reload_config(&self) {
+    let config = match Config::load() {
+      Ok(config) => config,
+      Err(error) => {
+        error!(
+          "Error reloading config (keeping the current config): {}",
+          error
+        );
+        return;
+      }
+    };
+
+    if config.background != self.config().background {
+      if let Some(mut swaybg) = self.swaybg.borrow_mut().take() {
+        if let Err(error) = swaybg.kill() {
+          warn!("Could not stop swaybg: {}", error);
+        }
+      }
+      *self.swaybg.borrow_mut() = BackgroundConfig::init(&config, self.config_manager.clone());
+    }
+
+    let current_layout = self.config_manager.config().keyboard.clone();
+    if !config.keyboard_layouts.contains(&current_layout) {
+      let layout = config.keyboard_layouts.first().cloned().unwrap_or_default();
+      self.config_manager.update_config(move |c| {
+        c.keyboard = layout;
+      });
+    }
+
+    self.animation_manager.set_config(config.animations.clone());
+    *self.config.borrow_mut() = config;
+    apply_all_device_configs(self);
+    self.keyboard.reset_sequence();
+    if let Some(mode) = self.keyboard.mode() {
+      if !self.config().keyboard_modes.contains_key(&mode) {
+        exit_mode(self);
+      }
+    }
+    self.update_extra_workspaces();
+    info!("Reloaded config from {}", Config::path());
+    self.broadcast_ipc_event(|| IpcEvent::ConfigReloaded);
+  }
+
+  /// Sends an event to all subscribed IPC clients.
+  ///
+  /// The event is only built if there is someone listening.
+  pub(crate) fn broadcast_ipc_event(&self, event: impl FnOnce() -> IpcEvent) {
+    if let Some(ref ipc_server) = self.ipc_server {
+      if ipc_server.has_subscribers() {
+        ipc_server.broadcast(event());
+      }
+    }
+  }
+
+  fn dispatch_ipc(&self) {
+    if let Some(ref ipc_server) = self.ipc_server {
+      ipc_server.dispatch(|request| ipc::handle_request(self, request));
+    }
+  }
+
+  fn handle_frame(&self) {
+    self.session.save_if_dirty(self);
+  }
+
+  /// How many workspaces to keep that are not shown on any output
+  fn expected_extra_workspaces(&self) -> usize {
+    cmp::max(self.config().extra_workspaces, 1)
+  }
+
+  /// Adds empty workspaces until there are expected_extra_workspaces besides
+  /// the shown_workspaces that are, or are about to be, shown on an output
+  fn add_extra_workspaces(
+    &self,
+    mru_workspaces: &mut MruList<Rc<Workspace>>,
+    shown_workspaces: usize,
+  ) {
+    while mru_workspaces.len() < shown_workspaces + self.expected_extra_workspaces() {
+      let index = next_workspace_index(mru_workspaces);
+      mru_workspaces.push_bottom(Rc::new(Workspace::new(index)));
+    }
+  }
+
+  /// Adds or removes hidden workspaces so that the number of workspaces not
+  /// shown on any output matches the configured extra_workspaces.
+  ///
+  /// Only empty workspaces are removed, least recently used first.
+  fn update_extra_workspaces(&self) {
+    let shown_workspaces = self.output_workspaces.borrow().len();
+    let mut mru_workspaces = self.mru_workspaces.borrow_mut();
+    self.add_extra_workspaces(&mut mru_workspaces, shown_workspaces);
+    while mru_workspaces.len() > shown_workspaces + self.expected_extra_workspaces() {
+      let removable_workspace = mru_workspaces
+        .iter()
+        .rev()
+        .find(|w| w.all_windows().is_empty() && self.output_by_workspace(w).is_none())
+        .cloned();
+      if let Some(workspace) = removable_workspace {
+        mru_workspaces.remove(&workspace);
+      } else {
+        break;
+      }
     }
   }
 
   pub(crate) fn focus_workspace(&self, workspace: &Rc<Workspace>) {
+    self.focus_workspace_from(workspace, None);
+  }
+
+  /// Focuses the workspace, sliding it in from the direction if it replaces
+  /// another workspace on the output
+  pub(crate) fn focus_workspace_from(
+    &self,
+    workspace: &Rc<Workspace>,
+    direction: Option<VerticalDirection>,
+  ) {
+    self.show_workspace(workspace, direction);
+    let top_window = workspace.mru_windows().top().cloned();
+    if let Some(window) = top_window {
+      self.window_manager.focus_window(window);
+    } else {
+      self.window_manager.blur();
+    }
+  }
+
+  /// Makes the workspace active and shows it on an output, without changing
+  /// the focused window.
+  pub(crate) fn show_workspace(
+    &self,
+    workspace: &Rc<Workspace>,
+    direction: Option<VerticalDirection>,
+  ) {
+    let mut changed = self.mru_workspaces().top() != Some(workspace);
     if self.output_by_workspace(workspace).is_none() {
       let output = self
         .mru_workspaces()
         .top()
         .and_then(|active_workspace| self.output_by_workspace(active_workspace));
       if let Some(output) = output {
-        self
+        let previous_workspace = self
           .output_workspaces
           .borrow_mut()
-          .insert(output, workspace.clone());
+          .insert(output.clone(), workspace.clone());
+        self.slide_workspaces(previous_workspace, workspace, &output, direction);
+        changed = true;
       } else {
         warn!("Focusing workspace not on any monitor");
       }
     }
     self.mru_workspaces.borrow_mut().promote(workspace);
-    let top_window = workspace.mru_windows().top().cloned();
-    if let Some(window) = top_window {
-      self.window_manager.focus_window(window);
-    } else {
-      self.window_manager.blur();
+    if changed {
+      self.broadcast_ipc_event(|| IpcEvent::WorkspaceFocused {
+        workspace: ipc::workspace_info(self, workspace),
+        workspaces: self
+          .mru_workspaces()
+          .iter()
+          .map(|workspace| ipc::workspace_info(self, workspace))
+          .collect(),
+      });
     }
   }
 
+  /// Shows the workspace on the output while cycling windows, without making
+  /// it the active workspace
+  fn preview_workspace(&self, workspace: &Rc<Workspace>, output: &Rc<Output>) {
+    if self.output_by_workspace(workspace).is_some() {
+      return;
+    }
+    let previous_workspace = self
+      .output_workspaces
+      .borrow_mut()
+      .insert(output.clone(), workspace.clone());
+    self.slide_workspaces(previous_workspace, workspace, output, None);
+  }
+
+  fn slide_workspaces(
+    &self,
+    from: Option<Rc<Workspace>>,
+    to: &Rc<Workspace>,
+    output: &Output,
+    direction: Option<VerticalDirection>,
+  ) {
+    let config = match self.animation_manager.config().workspace_slide.clone() {
+      Some(config) => config,
+      None => {
+        if let Some(from) = from {
+          from.set_windows_hidden(true);
+        }
+        to.set_windows_hidden(false);
+        return;
+      }
+    };
+    // Without a direction, workspaces with higher indices are below
+    let direction = direction.unwrap_or_else(|| match from {
+      Some(ref from) if from.index() > to.index() => VerticalDirection::Up,
+      _ => VerticalDirection::Down,
+    });
+    let distance = match direction {
+      VerticalDirection::Up => -output.extents().height(),
+      VerticalDirection::Down => output.extents().height(),
+    };
+    self
+      .animation_manager
+      .slide_workspaces(from, to.clone(), distance, &config);
+    arrange_windows_workspace(self, to.clone());
+  }
+
+  /// The focused window, or the previewed window while cycling windows
   pub(crate) fn active_window(&self) -> Option<Rc<Window>> {
+    if let Some(ref cycle) = *self.window_cycle.borrow() {
+      return Some(cycle.current());
+    }
     self.mru_windows.borrow().top().cloned()
   }
+  pub(crate) fn mru_windows(&self) -> Ref<MruList<Rc<Window>>> {
+    self.mru_windows.borrow()
+  }
+  /// Adds a window that is shown again, such as from the scratchpad, to the
+  /// most recently used windows
+  pub(crate) fn add_mru_window(&self, window: Rc<Window>) {
+    let mut mru_windows = self.mru_windows.borrow_mut();
+    if !mru_windows.iter().any(|w| *w == window) {
+      mru_windows.push(window);
+    }
+  }
+  /// Removes a hidden window, so that it is not focused until it is shown
+  /// again
+  pub(crate) fn remove_mru_window(&self, window: &Rc<Window>) {
+    self.mru_windows.borrow_mut().remove(window);
+  }
+  /// Makes the window the most recently used, globally and in its workspace
+  pub(crate) fn promote_window(&self, window: &Rc<Window>) {
+    self.mru_windows.borrow_mut().promote(window);
+    if let Some(workspace) = self.workspace_by_window(window) {
+      workspace.promote_window(window);
+    }
+  }
   pub(crate) fn mru_workspaces(&self) -> Ref<MruList<Rc<Workspace>>> {
     self.mru_workspaces.borrow()
   }
+  /// Finds the workspace with the specified index, creating it if needed.
+  pub(crate) fn workspace_by_index(&self, index: usize) -> Rc<Workspace> {
+    let workspace = self
+      .mru_workspaces()
+      .iter()
+      .find(|w| w.index() == index)
+      .cloned();
+    workspace.unwrap_or_else(|| {
+      let workspace = Rc::new(Workspace::new(index));
+      self
+        .mru_workspaces
+        .borrow_mut()
+        .push_bottom(workspace.clone());
+      workspace
+    })
+  }
+  /// Finds the workspace with the specified index or name, creating it if needed.
+  pub(crate) fn workspace_by_selector(
+    &self,
+    selector: &WorkspaceSelector,
+  ) -> Option<Rc<Workspace>> {
+    let index = self.config().workspace_index(selector);
+    match index {
+      Some(index) => Some(self.workspace_by_index(index)),
+      None => {
+        warn!("No workspace matches {:?}", selector);
+        None
+      }
+    }
+  }
   pub(crate) fn workspace_by_window(&self, window: &Window) -> Option<Rc<Workspace>> {
     self
       .mru_workspaces()
@@ -115,21 +452,108 @@ impl CascadeWindowManager {
         }
       })
   }
+  /// The part of the output that tiled windows are placed in, excluding
+  /// panels and docks
+  pub(crate) fn layout_area(&self, output: &Output) -> Rectangle {
+    self.config().gaps.layout_area(output.usable_area())
+  }
+  /// The initial column width a window rule asks for, on the output of the
+  /// workspace or else the active output
+  pub(crate) fn rule_width(&self, rule: &WindowRule, workspace: &Workspace) -> Option<i32> {
+    let width = rule.width?;
+    let output = self.output_by_workspace(workspace).or_else(|| {
+      self
+        .mru_workspaces()
+        .top()
+        .and_then(|active_workspace| self.output_by_workspace(active_workspace))
+    })?;
+    let layout_area = self.layout_area(&output);
+    Some(self.config().gaps.column_width(&layout_area, width))
+  }
 }
 
 impl WindowManagementPolicy for CascadeWindowManager {
   fn handle_window_ready(&self, window: Rc<Window>) {
-    if window.can_receive_focus() {
-      self.mru_windows.borrow_mut().push(window.clone());
+    if is_layer_surface(&window) {
+      layer_shell::handle_layer_surface_ready(self, window);
+    } else if window.can_receive_focus() {
+      let rule = WindowRule::for_window(&self.config().window_rules, &window);
+      let focus = rule.focus.unwrap_or(true);
 
-      let active_workspace = self
-        .mru_workspaces()
-        .top()
-        .cloned()
-        .expect("There should be at least one workspace");
-      active_workspace.add_window(window.clone(), WorkspacePosition::ActiveWindow);
+      if focus {
+        self.mru_windows.borrow_mut().push(window.clone());
+      } else {
+        self.mru_windows.borrow_mut().push_bottom(window.clone());
+      }
 
-      self.window_manager.focus_window(window);
+      if let Some(name) = rule.scratchpad.clone() {
+        let active_workspace = self
+          .mru_workspaces()
+          .top()
+          .cloned()
+          .expect("There should be at least one workspace");
+        self.scratchpad.add(window.clone(), Some(name));
+        show_scratchpad_window(self, window.clone(), &active_workspace);
+      } else if !self.session.restore_window(self, &window, &rule) {
+        let active_workspace = self
+          .mru_workspaces()
+          .top()
+          .cloned()
+          .expect("There should be at least one workspace");
+        let workspace = rule
+          .workspace
+          .as_ref()
+          .and_then(|selector| self.workspace_by_selector(selector))
+          .unwrap_or_else(|| active_workspace.clone());
+        let position = match rule.position {
+          Some(RulePosition::Start) => WorkspacePosition::Start,
+          Some(RulePosition::End) => WorkspacePosition::End,
+          Some(RulePosition::AfterActive) | None => WorkspacePosition::ActiveWindow,
+        };
+
+        if let Some(width) = self.rule_width(&rule, &workspace) {
+          window.resize(window.size().with_width(width));
+        }
+
+        let previous_window = workspace.mru_windows().top().cloned();
+        if rule.floating == Some(true) {
+          workspace.add_floating_window(window.clone());
+          if let Some(output) = self.output_by_workspace(&workspace) {
+            center_floating_window(self, window.clone(), &output);
+          }
+        } else {
+          workspace.add_window(window.clone(), position);
+        }
+        if !focus {
+          if let Some(previous_window) = previous_window {
+            workspace.promote_window(&previous_window);
+          }
+          arrange_windows_workspace(self, workspace);
+        }
+      }
+      // Windows opened on a workspace that is not shown stay hidden until it
+      // is
+      let workspace = self.workspace_by_window(&window);
+      if let Some(workspace) = workspace {
+        if self.output_by_workspace(&workspace).is_none() {
+          window.set_hidden(true);
+        }
+      }
+      self.broadcast_ipc_event(|| IpcEvent::WindowOpened {
+        window: ipc::window_info(self, &window),
+      });
+
+      let mut fullscreen = false;
+      self.pending_fullscreen.borrow_mut().retain(|w| {
+        fullscreen |= *w == window;
+        *w != window
+      });
+      if focus {
+        self.window_manager.focus_window(window.clone());
+        if fullscreen {
+          set_window_fullscreen(self, window, true);
+        }
+      }
     }
   }
   fn advise_configured_window(&self, window: Rc<Window>) {
@@ -139,16 +563,78 @@ impl WindowManagementPolicy for CascadeWindowManager {
     }
   }
   fn advise_focused_window(&self, window: Rc<Window>) {
-    self.mru_windows.borrow_mut().promote(&window);
+    // Layer surfaces are not part of any workspace, and are never the active
+    // window
+    if is_layer_surface(&window) || !layer_shell::advise_focused_window(self, &window) {
+      return;
+    }
+    // A hidden scratchpad window, e.g. activated by the client, is shown
+    // where the user is
+    if self.scratchpad.contains(&window) && self.workspace_by_window(&window).is_none() {
+      if let Some(active_workspace) = self.mru_workspaces().top().cloned() {
+        show_scratchpad_window(self, window.clone(), &active_workspace);
+      }
+    }
+    // While cycling windows the focus is only previewed, and the order of
+    // mru_windows is kept until the cycle finishes
+    let previewing = match *self.window_cycle.borrow() {
+      Some(ref cycle) => cycle.current() == window,
+      None => false,
+    };
+    if !previewing {
+      *self.window_cycle.borrow_mut() = None;
+      self.mru_windows.borrow_mut().promote(&window);
+    }
     let workspace = self.workspace_by_window(&window);
     if let Some(workspace) = workspace {
-      workspace.promote_window(&window);
-      self.focus_workspace(&workspace);
+      // Focusing another window in the strip would leave it hidden behind the
+      // fullscreen window
+      if let Some(fullscreen_window) = workspace.fullscreen_window() {
+        if fullscreen_window != window && !workspace.is_floating(&window) {
+          set_window_fullscreen(self, fullscreen_window, false);
+        }
+      }
+      if previewing {
+        let output = self
+          .window_cycle
+          .borrow()
+          .as_ref()
+          .and_then(|cycle| cycle.output());
+        if let Some(output) = output {
+          self.preview_workspace(&workspace, &output);
+        }
+      } else {
+        workspace.promote_window(&window);
+        self.focus_workspace(&workspace);
+      }
       arrange_windows_workspace(self, workspace.clone());
     }
+    self.broadcast_ipc_event(|| IpcEvent::WindowFocused {
+      window: ipc::window_info(self, &window),
+    });
   }
   fn advise_delete_window(&self, window: Rc<Window>) {
+    if is_layer_surface(&window) {
+      layer_shell::advise_delete_layer_surface(self, window);
+      return;
+    }
+    self.broadcast_ipc_event(|| IpcEvent::WindowClosed {
+      window: ipc::window_info(self, &window),
+    });
     self.mru_windows.borrow_mut().remove(&window);
+    self
+      .pending_fullscreen
+      .borrow_mut()
+      .retain(|w| *w != window);
+    self.scratchpad.remove(&window);
+    let cycle_removed = self
+      .window_cycle
+      .borrow_mut()
+      .as_mut()
+      .map_or(false, |cycle| !cycle.remove(&window));
+    if cycle_removed {
+      *self.window_cycle.borrow_mut() = None;
+    }
 
     let workspace = self
       .mru_workspaces()
@@ -170,24 +656,42 @@ impl WindowManagementPolicy for CascadeWindowManager {
   }
 
   fn advise_output_create(&self, output: Rc<Output>) {
+    let restored_workspace = self
+      .session
+      .workspace_for_output(&output.name())
+      .map(|state| (state.index, state.scroll_left))
+      .map(|(index, scroll_left)| (self.workspace_by_index(index), scroll_left))
+      .filter(|(workspace, _)| self.output_by_workspace(workspace).is_none())
+      .map(|(workspace, scroll_left)| {
+        workspace.set_scroll_left(scroll_left);
+        workspace
+      });
+
+    // The new output is about to show one of the workspaces
+    let shown_workspaces = self.output_workspaces.borrow().len() + 1;
     let mut mru_workspaces = self.mru_workspaces.borrow_mut();
-    let expected_extra_workspaces = cmp::max(self.config.extra_workspaces, 1);
-    while mru_workspaces.len() - self.output_workspaces.borrow().len() <= expected_extra_workspaces
-    {
-      mru_workspaces.push_bottom(Rc::new(Workspace::new()));
-    }
-    let first_unused_workspace = mru_workspaces
-      .iter()
-      .find(|w| self.output_by_workspace(w).is_none())
-      .cloned()
-      .expect("There should be at least one unused workspace");
+    self.add_extra_workspaces(&mut mru_workspaces, shown_workspaces);
+    let workspace = restored_workspace.unwrap_or_else(|| {
+      mru_workspaces
+        .iter()
+        .find(|w| self.output_by_workspace(w).is_none())
+        .cloned()
+        .expect("There should be at least one unused workspace")
+    });
 
     self
       .output_workspaces
       .borrow_mut()
-      .insert(output, first_unused_workspace.clone());
-    arrange_windows_workspace(self, first_unused_workspace);
+      .insert(output.clone(), workspace.clone());
+    drop(mru_workspaces);
+    workspace.set_windows_hidden(false);
+    arrange_windows_workspace(self, workspace);
+    self.broadcast_ipc_event(|| IpcEvent::OutputAdded {
+      output: ipc::output_info(self, &output),
+    });
   }
+  /// Also called when the usable area of the output changes, as layer
+  /// surfaces with exclusive zones are mapped, resized or unmapped
   fn advise_output_update(&self, output: Rc<Output>) {
     let workspace = self
       .output_workspaces
@@ -198,6 +702,7 @@ impl WindowManagementPolicy for CascadeWindowManager {
     arrange_windows_workspace(self, workspace);
   }
   fn advise_output_delete(&self, output: Rc<Output>) {
+    let output_info = ipc::output_info(self, &output);
     let mru_workspaces = self.mru_workspaces();
     let mru_outputs = mru_workspaces
       .iter()
@@ -208,7 +713,14 @@ impl WindowManagementPolicy for CascadeWindowManager {
       .zip(mru_workspaces.iter().cloned())
       .collect::<BTreeMap<_, _>>();
     *self.output_workspaces.borrow_mut() = output_workspaces;
+    for workspace in mru_workspaces.iter() {
+      workspace.set_windows_hidden(self.output_by_workspace(workspace).is_none());
+    }
+    drop(mru_workspaces);
     arrange_windows_all_workspaces(self);
+    self.broadcast_ipc_event(|| IpcEvent::OutputRemoved {
+      output: output_info,
+    });
   }
 
   fn handle_request_move(&self, request: MoveRequest) {
@@ -221,7 +733,7 @@ impl WindowManagementPolicy for CascadeWindowManager {
       request.window.set_maximized(false);
     }
     if request.window.fullscreen() {
-      request.window.set_fullscreen(false);
+      set_window_fullscreen(self, request.window.clone(), false);
     }
 
     *self.gesture.borrow_mut() = Gesture::Move(request)
@@ -247,11 +759,31 @@ impl WindowManagementPolicy for CascadeWindowManager {
 
     resize_window(self, request.window, &vec![1.0]);
   }
+  fn handle_request_fullscreen(&self, request: FullscreenRequest) {
+    let window = request.window;
+    if !self.mru_windows().iter().any(|w| *w == window) {
+      // Clients often ask before they are mapped. The request is granted when
+      // the window is ready, if it is going to be focused then.
+      let mut pending_fullscreen = self.pending_fullscreen.borrow_mut();
+      pending_fullscreen.retain(|w| *w != window);
+      if request.fullscreen {
+        pending_fullscreen.push(window);
+      }
+      return;
+    }
+    let about_to_be_focused = self.active_window().as_ref() == Some(&window);
+    if !self.window_manager.window_has_focus(&window) && !about_to_be_focused {
+      // Deny fullscreen requests from unfocused clients
+      return;
+    }
+
+    set_window_fullscreen(self, window, request.fullscreen);
+  }
 }
 
 impl EventFilter for CascadeWindowManager {
   fn handle_keyboard_event(&self, event: &KeyboardEvent) -> bool {
-    handle_key_press(self, event)
+    handle_key_event(self, event)
   }
   fn handle_pointer_motion_event(&self, event: &MotionEvent) -> bool {
     pointer::handle_motion_event(self, event)
@@ -260,3 +792,65 @@ impl EventFilter for CascadeWindowManager {
     pointer::handle_button_event(self, event)
   }
 }
+
+fn next_workspace_index(mru_workspaces: &MruList<Rc<Workspace>>) -> usize {
+  (1..)
+    .find(|index| !mru_workspaces.iter().any(|w| w.index() == *index))
+    .expect("There should be an unused workspace index")
+}
+
+/// Passes compositor callbacks on to a shared [`CascadeWindowManager`].
+///
+/// The compositor takes ownership of its window management policy, but output
+/// frame listeners need to reach the window manager too.
+pub(crate) struct SharedWindowManager(pub(crate) Rc<CascadeWindowManager>);
+
+impl WindowManagementPolicy for SharedWindowManager {
+  fn handle_window_ready(&self, window: Rc<Window>) {
+    self.0.handle_window_ready(window)
+  }
+  fn advise_configured_window(&self, window: Rc<Window>) {
+    self.0.advise_configured_window(window)
+  }
+  fn advise_focused_window(&self, window: Rc<Window>) {
+    self.0.advise_focused_window(window)
+  }
+  fn advise_delete_window(&self, window: Rc<Window>) {
+    self.0.advise_delete_window(window)
+  }
+
+  fn advise_output_create(&self, output: Rc<Output>) {
+    self.0.advise_output_create(output)
+  }
+  fn advise_output_update(&self, output: Rc<Output>) {
+    self.0.advise_output_update(output)
+  }
+  fn advise_output_delete(&self, output: Rc<Output>) {
+    self.0.advise_output_delete(output)
+  }
+
+  fn handle_request_move(&self, request: MoveRequest) {
+    self.0.handle_request_move(request)
+  }
+  fn handle_request_resize(&self, request: ResizeRequest) {
+    self.0.handle_request_resize(request)
+  }
+  fn handle_request_maximize(&self, request: MaximizeRequest) {
+    self.0.handle_request_maximize(request)
+  }
+  fn handle_request_fullscreen(&self, request: FullscreenRequest) {
+    self.0.handle_request_fullscreen(request)
+  }
+}
+
+impl EventFilter for SharedWindowManager {
+  fn handle_keyboard_event(&self, event: &KeyboardEvent) -> bool {
+    self.0.handle_keyboard_event(event)
+  }
+  fn handle_pointer_motion_event(&self, event: &MotionEvent) -> bool {
+    self.0.handle_pointer_motion_event(event)
+  }
+  fn handle_pointer_button_event(&self, event: &ButtonEvent) -> bool {
+    self.0.handle_pointer_button_event(event)
+  }
+}
@@ -0,0 +1,202 @@
+use crate::{config::Config, shortcuts::WorkspaceSelector};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use wlral::window::Window;
+
+/// A regular expression that is compiled when the config is loaded.
+#[derive(Debug, Clone)]
+pub(crate) struct Pattern(Regex);
+
+impl Pattern {
+  /// A pattern that only matches the exact text
+  pub(crate) fn exact(text: &str) -> Pattern {
+    Pattern(
+      Regex::new(&format!("^{}$", regex::escape(text))).expect("Escaped text is a valid regex"),
+    )
+  }
+
+  pub(crate) fn is_match(&self, text: &str) -> bool {
+    self.0.is_match(text)
+  }
+}
+
+impl PartialEq for Pattern {
+  fn eq(&self, other: &Pattern) -> bool {
+    self.0.as_str() == other.0.as_str()
+  }
+}
+
+impl Serialize for Pattern {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    self.0.as_str().serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    use serde::de::Error;
+    let pattern: String = Deserialize::deserialize(deserializer)?;
+    Regex::new(&pattern)
+      .map(Pattern)
+      .map_err(|error| Error::custom(format!("Invalid pattern \"{}\": {}", pattern, error)))
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RulePosition {
+  Start,
+  End,
+  AfterActive,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WindowRule {
+  pub(crate) app_id: Option<Pattern>,
+  pub(crate) title: Option<Pattern>,
+  /// Initial width as a fraction of the output width
+  pub(crate) width: Option<f32>,
+  /// Index or name of the workspace to open the window on
+  pub(crate) workspace: Option<WorkspaceSelector>,
+  pub(crate) position: Option<RulePosition>,
+  pub(crate) floating: Option<bool>,
+  pub(crate) focus: Option<bool>,
+  /// Name to put the window in the scratchpad under, shown when it opens
+  pub(crate) scratchpad: Option<String>,
+}
+
+impl WindowRule {
+  pub(crate) fn validate(config: &Config) -> Result<(), Box<dyn Error>> {
+    for (i, rule) in config.window_rules.iter().enumerate() {
+      if rule.app_id.is_none() && rule.title.is_none() {
+        return Err(format!("window_rules[{}]: app_id or title must be specified", i).into());
+      }
+      if let Some(width) = rule.width {
+        if width <= 0.0 || width > 1.0 {
+          return Err(format!("window_rules[{}].width: must be between 0 and 1", i).into());
+        }
+      }
+      if let Some(ref workspace) = rule.workspace {
+        if config.workspace_index(workspace).is_none() {
+          let message = match workspace {
+            WorkspaceSelector::Index(_) => "indices start at 1".to_string(),
+            WorkspaceSelector::Name(name) => format!("no workspace is named \"{}\"", name),
+          };
+          return Err(format!("window_rules[{}].workspace: {}", i, message).into());
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  pub(crate) fn matches(&self, window: &Window) -> bool {
+    self.matches_ids(window.app_id().as_deref(), window.title().as_deref())
+  }
+
+  fn matches_ids(&self, app_id: Option<&str>, title: Option<&str>) -> bool {
+    fn pattern_matches(pattern: &Option<Pattern>, value: Option<&str>) -> bool {
+      match pattern {
+        Some(pattern) => value.map_or(false, |value| pattern.is_match(value)),
+        None => true,
+      }
+    }
+
+    pattern_matches(&self.app_id, app_id) && pattern_matches(&self.title, title)
+  }
+
+  /// Combines all rules that match the window.
+  ///
+  /// Rules are applied in order, so later rules override earlier ones.
+  pub(crate) fn for_window(rules: &[WindowRule], window: &Window) -> WindowRule {
+    WindowRule::merge(rules.iter().filter(|rule| rule.matches(window)))
+  }
+
+  fn merge<'a>(rules: impl Iterator<Item = &'a WindowRule>) -> WindowRule {
+    rules.fold(WindowRule::default(), |merged, rule| WindowRule {
+      app_id: None,
+      title: None,
+      width: rule.width.or(merged.width),
+      workspace: rule.workspace.clone().or(merged.workspace),
+      position: rule.position.or(merged.position),
+      floating: rule.floating.or(merged.floating),
+      focus: rule.focus.or(merged.focus),
+      scratchpad: rule.scratchpad.clone().or(merged.scratchpad),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse_rule(yaml: &str) -> WindowRule {
+    serde_yaml::from_str(yaml).unwrap()
+  }
+
+  #[test]
+  fn patterns_match_anywhere_unless_anchored() {
+    let rule = parse_rule("app_id: fire");
+    assert!(rule.matches_ids(Some("firefox"), None));
+    assert!(rule.matches_ids(Some("org.mozilla.firefox"), None));
+
+    let rule = parse_rule("app_id: ^firefox$");
+    assert!(rule.matches_ids(Some("firefox"), None));
+    assert!(!rule.matches_ids(Some("org.mozilla.firefox"), None));
+  }
+
+  #[test]
+  fn exact_patterns_escape_the_text() {
+    let pattern = Pattern::exact("a.b");
+    assert!(pattern.is_match("a.b"));
+    assert!(!pattern.is_match("axb"));
+    assert!(!pattern.is_match("a.bc"));
+  }
+
+  #[test]
+  fn all_patterns_must_match() {
+    let rule = parse_rule("{ app_id: firefox, title: Picture-in-Picture }");
+    assert!(rule.matches_ids(Some("firefox"), Some("Picture-in-Picture")));
+    assert!(!rule.matches_ids(Some("firefox"), Some("Mozilla Firefox")));
+    assert!(!rule.matches_ids(Some("chromium"), Some("Picture-in-Picture")));
+  }
+
+  #[test]
+  fn missing_ids_do_not_match_patterns() {
+    assert!(!parse_rule("app_id: .*").matches_ids(None, Some("title")));
+    assert!(!parse_rule("title: .*").matches_ids(Some("app"), None));
+  }
+
+  #[test]
+  fn invalid_patterns_are_rejected() {
+    assert!(serde_yaml::from_str::<WindowRule>("app_id: \"(\"").is_err());
+  }
+
+  #[test]
+  fn later_rules_override_earlier_ones() {
+    let rules = vec![
+      parse_rule("{ app_id: a, width: 0.5, floating: true, position: start }"),
+      parse_rule("{ app_id: a, width: 0.25, focus: false }"),
+    ];
+    let merged = WindowRule::merge(rules.iter());
+    assert_eq!(merged.width, Some(0.25));
+    assert_eq!(merged.floating, Some(true));
+    assert_eq!(merged.position, Some(RulePosition::Start));
+    assert_eq!(merged.focus, Some(false));
+    assert_eq!(merged.workspace, None);
+    assert_eq!(merged.app_id, None);
+  }
+
+  #[test]
+  fn no_rules_merge_to_the_default() {
+    assert_eq!(WindowRule::merge(std::iter::empty()), WindowRule::default());
+  }
+}